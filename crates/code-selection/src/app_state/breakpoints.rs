@@ -0,0 +1,88 @@
+use crate::*;
+use macroquad::prelude::*;
+use std::path::Path;
+
+impl AppState {
+    /// With a cell selected in the inspector:
+    /// - `O` breaks when the cell executes the opcode of the byte picked in the inspector,
+    ///   or at its program counter if no byte is picked
+    /// - `M` breaks when the byte picked in the inspector changes
+    /// - `H` breaks when any other cell gets the same memory
+    ///
+    /// `N` breaks on any write into a neighbor, `Backspace` removes all breakpoints.
+    pub fn handle_breakpoints(&mut self) {
+        if is_key_pressed(KeyCode::Backspace) {
+            for breakpoint in self.breakpoints.drain(..).rev() {
+                breakpoint.release(&mut self.world);
+            }
+            self.triggered_breakpoint = None;
+        }
+
+        if is_key_pressed(KeyCode::N) {
+            self.breakpoints.push(Breakpoint::NeighborWrite);
+        }
+
+        let Some(cell) = self.selected_cell else {
+            return;
+        };
+
+        if is_key_pressed(KeyCode::O) {
+            let address = self.inspected_address.unwrap_or_else(|| {
+                let pc = self.world.cells[cell].registers[CellState::REGISTER_PROGRAM_COUNTER];
+                // the opcode may be in the neighbor which changes every tick, use own memory only
+                pc as usize % self.world.vm_config.memory_size
+            });
+            let opcode = self.world.cells[cell].memory[address];
+
+            self.breakpoints.push(Breakpoint::opcode(cell, opcode));
+        }
+
+        if is_key_pressed(KeyCode::M) {
+            if let Some(address) = self.inspected_address {
+                self.breakpoints
+                    .push(Breakpoint::memory_change(&self.world, cell, address));
+            }
+        }
+
+        if is_key_pressed(KeyCode::H) {
+            let hash = self.world.cells[cell].memory_hash();
+            self.breakpoints
+                .push(Breakpoint::memory_hash(&self.world, hash));
+        }
+    }
+
+    /// Break when any cell gets the memory of the pattern file, a program or a saved cell
+    /// loaded the same way as stamps.
+    pub fn break_on_pattern(&mut self, path: &Path) -> Result<(), String> {
+        let stamp = Stamp::load(path, &self.world.vm_config)?;
+        self.breakpoints.push(Breakpoint::memory_hash(
+            &self.world,
+            stamp.cell.memory_hash(),
+        ));
+
+        Ok(())
+    }
+
+    /// Pause and focus on the cell if any breakpoint fired during the last tick.
+    pub fn check_breakpoints(&mut self) -> bool {
+        let triggered = self
+            .breakpoints
+            .iter_mut()
+            .find_map(|breakpoint| Some((breakpoint.check(&self.world)?, breakpoint.clone())));
+
+        let Some((cell, breakpoint)) = triggered else {
+            return false;
+        };
+
+        self.is_paused = true;
+        self.triggered_breakpoint = Some(breakpoint);
+        if self.selected_cell != Some(cell) {
+            self.selected_cell = Some(cell);
+            self.inspected_address = None;
+        }
+        self.center_on_cell(cell);
+        self.update_texture();
+
+        true
+    }
+}
@@ -0,0 +1,279 @@
+use crate::*;
+use macroquad::prelude::*;
+
+impl AppState {
+    pub const INSPECTOR_WIDTH: f32 = 300.0;
+    const INSPECTOR_LINE_HEIGHT: f32 = 18.0;
+    /// Number of lines above the disassembly.
+    const INSPECTOR_HEADER_LINES: usize = 7;
+    /// Number of disassembly lines shown before the program counter.
+    const INSPECTOR_LINES_BEFORE_PC: usize = 6;
+    const INSPECTOR_DISASSEMBLY_LINES: usize = 28;
+
+    /// Left click selects the cell under the cursor or the address in the inspector,
+    /// right click closes the inspector, `G` saves the control-flow graph of the selected cell.
+    pub fn handle_cell_selection(&mut self) {
+        if let Some(index) = self.selected_cell {
+            if is_key_pressed(KeyCode::G) {
+                self.save_control_flow_graph(index);
+            }
+        }
+
+        if is_mouse_button_pressed(MouseButton::Right) {
+            self.selected_cell = None;
+            self.inspected_address = None;
+        }
+
+        if !is_mouse_button_pressed(MouseButton::Left) || self.stamp_tool.is_some() {
+            return;
+        }
+
+        let (x, y) = mouse_position();
+        if let Some(index) = self.selected_cell {
+            if x >= screen_width() - Self::INSPECTOR_WIDTH {
+                let line = ((y - 4.0) / Self::INSPECTOR_LINE_HEIGHT).max(0.0) as usize;
+                let instruction = line
+                    .checked_sub(Self::INSPECTOR_HEADER_LINES)
+                    .and_then(|line| self.get_inspector_disassembly(index).get(line).copied());
+
+                if let Some(instruction) = instruction {
+                    self.inspected_address = Some(instruction.address);
+                }
+
+                return;
+            }
+        }
+
+        if let Some(index) = self.get_cell_at_screen_position(x, y) {
+            if self.selected_cell != Some(index) {
+                self.inspected_address = None;
+            }
+
+            self.selected_cell = Some(index);
+        }
+    }
+
+    /// Save the control-flow graph of the cell from its pc as a Graphviz DOT file.
+    fn save_control_flow_graph(&self, index: usize) {
+        let config = &self.world.vm_config;
+        let cell = &self.world.cells[index];
+
+        let pc =
+            cell.registers[CellState::REGISTER_PROGRAM_COUNTER] as usize % config.address_space();
+        if pc >= config.memory_size {
+            eprintln!("Cell #{index} has pc {pc:#04x} outside of its memory");
+            return;
+        }
+
+        let graph = ControlFlowGraph::build(&cell.memory, pc, config);
+        let path = format!("cell-{index}-tick-{}.dot", self.world.tick_count);
+
+        match std::fs::write(&path, graph.to_dot()) {
+            Ok(()) => println!("Saved control-flow graph of cell #{index} to {path}"),
+            Err(err) => eprintln!("Failed to save control-flow graph to {path}: {err}"),
+        }
+    }
+
+    /// Returns index of the cell drawn at the given screen position.
+    pub fn get_cell_at_screen_position(&self, x: f32, y: f32) -> Option<usize> {
+        let cell_size = self.world.vm_config.cell_canvas_size();
+        let canvas_pos = self.screen_to_canvas(vec2(x, y));
+
+        if canvas_pos.x < 0.0 || canvas_pos.y < 0.0 {
+            return None;
+        }
+
+        let cell_x = canvas_pos.x as usize / cell_size.width;
+        let cell_y = canvas_pos.y as usize / cell_size.height;
+
+        if cell_x >= self.world.size.width || cell_y >= self.world.size.height {
+            return None;
+        }
+
+        Some(
+            self.world
+                .size
+                .coords_to_index(RelativePosition::new(cell_x as u32, cell_y as u32)),
+        )
+    }
+
+    /// Returns screen rect of the cell with the given index.
+    pub fn get_cell_screen_rect(&self, index: usize) -> Rect {
+        let cell_size = self.world.vm_config.cell_canvas_size();
+        let scale = self.get_world_scale();
+        let pos = self.world.size.index_to_coords(index) * cell_size;
+        let screen_pos = self.canvas_to_screen(vec2(pos.x as f32, pos.y as f32));
+
+        Rect::new(
+            screen_pos.x,
+            screen_pos.y,
+            cell_size.width as f32 * scale,
+            cell_size.height as f32 * scale,
+        )
+    }
+
+    /// Returns the program counter of the cell wrapped around the address space.
+    fn get_inspector_pc(&self, index: usize) -> usize {
+        let registers = &self.world.cells[index].registers;
+        registers[CellState::REGISTER_PROGRAM_COUNTER] as usize
+            % self.world.vm_config.address_space()
+    }
+
+    /// Returns disassembly lines visible in the inspector.
+    fn get_inspector_disassembly(&self, index: usize) -> Vec<DisassembledInstruction> {
+        let pc = self.get_inspector_pc(index);
+        let disassembly = disassemble(
+            &self.world.cells[index].memory,
+            &self.world.vm_config,
+            Some(pc),
+        );
+
+        let pc_line = disassembly
+            .iter()
+            .position(|instruction| instruction.contains(pc))
+            .unwrap_or_default();
+        let first_line = pc_line.saturating_sub(Self::INSPECTOR_LINES_BEFORE_PC);
+
+        disassembly
+            .into_iter()
+            .skip(first_line)
+            .take(Self::INSPECTOR_DISASSEMBLY_LINES)
+            .collect()
+    }
+
+    pub fn draw_inspector(&self) {
+        let Some(index) = self.selected_cell else {
+            return;
+        };
+
+        let config = &self.world.vm_config;
+        let cell = &self.world.cells[index];
+        let pos = self.world.size.index_to_coords(index);
+
+        let rect = self.get_cell_screen_rect(index);
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, YELLOW);
+
+        let panel_x = screen_width() - Self::INSPECTOR_WIDTH;
+        draw_rectangle(
+            panel_x,
+            0.0,
+            Self::INSPECTOR_WIDTH,
+            screen_height(),
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+
+        let x = panel_x + 10.0;
+        let mut y = 0.0;
+        let mut line = |text: &str, color: Color| {
+            y += Self::INSPECTOR_LINE_HEIGHT;
+            draw_text_with_shadow(text, x, y, color);
+        };
+
+        let reg = |register: usize| cell.registers[register];
+        let flag = |mask: Word, name: &'static str| {
+            if reg(CellState::REGISTER_FLAGS) & mask != 0 {
+                name
+            } else {
+                "-"
+            }
+        };
+
+        line(&format!("Cell #{index} ({}, {})", pos.x, pos.y), WHITE);
+        line(
+            &format!(
+                "a={:#04x} pc={:#04x} sp={:#04x}",
+                reg(CellState::REGISTER_ACCUMULATOR),
+                reg(CellState::REGISTER_PROGRAM_COUNTER),
+                reg(CellState::REGISTER_STACK_POINTER),
+            ),
+            WHITE,
+        );
+        line(
+            &format!(
+                "b={:#04x} c={:#04x} d={:#04x} e={:#04x}",
+                reg(CellState::REGISTER_B),
+                reg(CellState::REGISTER_C),
+                reg(CellState::REGISTER_D),
+                reg(CellState::REGISTER_E),
+            ),
+            WHITE,
+        );
+        line(
+            &format!(
+                "f={:#04x} [{}{}{}]",
+                reg(CellState::REGISTER_FLAGS),
+                flag(CellState::FLAG_Z_MASK, "Z"),
+                flag(CellState::FLAG_N_MASK, "N"),
+                flag(CellState::FLAG_C_MASK, "C"),
+            ),
+            WHITE,
+        );
+
+        match self.world.last_partners[index] {
+            Some(partner) => {
+                let partner_pos = self.world.size.index_to_coords(partner as usize);
+                line(
+                    &format!(
+                        "Last partner: #{partner} ({}, {})",
+                        partner_pos.x, partner_pos.y
+                    ),
+                    WHITE,
+                );
+            }
+            None => line("Last partner: none", WHITE),
+        }
+
+        let inspected_origin = self.inspected_address.and_then(|address| {
+            let shadow = cell.provenance.as_ref()?;
+            Some((address, *shadow.memory.get(address)?))
+        });
+        match inspected_origin {
+            Some((address, origin)) => {
+                line(
+                    &format!(
+                        "{address:#04x} from #{} at tick {}",
+                        origin.cell, origin.tick
+                    ),
+                    ORANGE,
+                );
+            }
+            None => line("", WHITE),
+        }
+
+        let pc = self.get_inspector_pc(index);
+        let sp = match config.stack_mode {
+            StackMode::Wrap => {
+                Some(reg(CellState::REGISTER_STACK_POINTER) as usize % config.address_space())
+            }
+            StackMode::Separate { .. } | StackMode::Trap { .. } => None,
+        };
+
+        if pc >= config.memory_size {
+            line(&format!("pc is in the neighbor at {pc:#04x}"), YELLOW);
+        } else {
+            line("", WHITE);
+        }
+
+        for instruction in self.get_inspector_disassembly(index) {
+            let is_pc = instruction.contains(pc);
+            let is_sp = sp.is_some_and(|sp| instruction.contains(sp));
+            let is_inspected = self.inspected_address == Some(instruction.address);
+
+            let (marker, color) = match (is_pc, is_sp) {
+                (true, true) => ("pc sp", YELLOW),
+                (true, false) => ("pc", YELLOW),
+                (false, true) => ("sp", SKYBLUE),
+                (false, false) if is_inspected => ("*", ORANGE),
+                (false, false) => ("", WHITE),
+            };
+
+            line(
+                &format!(
+                    "{marker:>5} {:#04x}  {:02x}  {instruction}",
+                    instruction.address, instruction.opcode
+                ),
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::*;
+use macroquad::prelude::*;
+
+/// Value of a charted metric from a sample and the number of ticks between samples.
+type ChartMetric = fn(&MetricsSample, u64) -> f32;
+
+impl AppState {
+    const CHARTS_WIDTH: f32 = 360.0;
+    const CHART_HEIGHT: f32 = 70.0;
+    const CHART_TITLE_HEIGHT: f32 = 20.0;
+    /// Number of the most recent samples shown by the charts.
+    const CHART_SAMPLES: usize = 200;
+
+    pub fn handle_metric_charts_switch(&mut self) {
+        if is_key_pressed(KeyCode::V) {
+            self.show_metric_charts = !self.show_metric_charts;
+        }
+    }
+
+    /// Draw line charts of the recent metric samples scrolling to the left as new samples come.
+    pub fn draw_metric_charts(&self) {
+        let Some(metrics) = self
+            .world
+            .metrics
+            .as_ref()
+            .filter(|_| self.show_metric_charts)
+        else {
+            return;
+        };
+
+        let samples = &metrics.samples[metrics.samples.len().saturating_sub(Self::CHART_SAMPLES)..];
+        let charts: [(&str, ChartMetric, Color); 4] = [
+            (
+                "High-order entropy",
+                |sample, _| sample.entropy.high_order() as f32,
+                ORANGE,
+            ),
+            (
+                "Compressed size",
+                |sample, _| sample.entropy.compressed_size as f32,
+                SKYBLUE,
+            ),
+            (
+                "Unique genomes",
+                |sample, _| sample.census.unique_genomes as f32,
+                GREEN,
+            ),
+            (
+                "Instructions per tick",
+                |sample, interval| {
+                    sample.stats.executed.iter().sum::<u64>() as f32 / interval as f32
+                },
+                PINK,
+            ),
+        ];
+
+        let chart_step = Self::CHART_TITLE_HEIGHT + Self::CHART_HEIGHT + 5.0;
+        let height = charts.len() as f32 * chart_step + 5.0;
+        let inspector_width = match self.selected_cell {
+            Some(_) => Self::INSPECTOR_WIDTH,
+            None => 0.0,
+        };
+        let x = screen_width() - inspector_width - Self::CHARTS_WIDTH - 10.0;
+        let y = screen_height() - height - 10.0;
+        draw_rectangle(
+            x,
+            y,
+            Self::CHARTS_WIDTH,
+            height,
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+
+        for (index, (title, value, color)) in charts.iter().enumerate() {
+            let chart = Rect::new(
+                x + 10.0,
+                y + index as f32 * chart_step + Self::CHART_TITLE_HEIGHT,
+                Self::CHARTS_WIDTH - 20.0,
+                Self::CHART_HEIGHT,
+            );
+            let values = samples
+                .iter()
+                .map(|sample| value(sample, metrics.interval))
+                .collect::<Vec<_>>();
+            draw_line_chart(title, &values, chart, *color);
+        }
+    }
+}
+
+/// Draw the values scaled to their range with the latest value in the title.
+fn draw_line_chart(title: &str, values: &[f32], rect: Rect, color: Color) {
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 1.0, DARKGRAY);
+
+    let Some(&last) = values.last() else {
+        draw_text_with_shadow(&format!("{title}: no samples"), rect.x, rect.y - 5.0, WHITE);
+        return;
+    };
+    draw_text_with_shadow(&format!("{title}: {last:.3}"), rect.x, rect.y - 5.0, WHITE);
+
+    let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let range = (max - min).max(f32::EPSILON);
+    draw_text(format!("{max:.2}"), rect.x + 2.0, rect.y + 10.0, 12.0, GRAY);
+    draw_text(
+        format!("{min:.2}"),
+        rect.x + 2.0,
+        rect.y + rect.h - 2.0,
+        12.0,
+        GRAY,
+    );
+
+    let step = rect.w / (AppState::CHART_SAMPLES - 1) as f32;
+    let point = |index: usize, value: f32| {
+        vec2(
+            rect.x + index as f32 * step,
+            rect.y + rect.h - (value - min) / range * rect.h,
+        )
+    };
+
+    for (index, pair) in values.windows(2).enumerate() {
+        let from = point(index, pair[0]);
+        let to = point(index + 1, pair[1]);
+        draw_line(from.x, from.y, to.x, to.y, 1.5, color);
+    }
+}
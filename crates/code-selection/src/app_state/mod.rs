@@ -0,0 +1,515 @@
+mod breakpoints;
+mod inspector;
+mod metric_charts;
+mod opcode_heatmap;
+mod stamping;
+
+use crate::*;
+use macroquad::prelude::*;
+
+pub struct AppState {
+    pub world: World,
+    pub world_canvas: Image,
+    pub world_texture: Texture2D,
+
+    pub ticks_per_update: usize,
+
+    pub is_paused: bool,
+
+    /// Index of the cell shown in the inspector panel.
+    pub selected_cell: Option<usize>,
+    /// Memory address of the selected cell picked in the inspector.
+    pub inspected_address: Option<usize>,
+
+    pub breakpoints: Vec<Breakpoint>,
+    /// Breakpoint which paused the simulation last time.
+    pub triggered_breakpoint: Option<Breakpoint>,
+
+    /// Zoom relative to the size which fits the whole world on the screen.
+    pub view_zoom: f32,
+    /// Position of the top left screen corner on the world canvas.
+    pub view_offset: Vec2,
+
+    /// Replicators and fractions seeded into the world, seeded again on reset.
+    pub seeded_replicators: Vec<(&'static Replicator, f64)>,
+
+    pub stamps: Vec<Stamp>,
+    pub selected_stamp: usize,
+    /// Left mouse button paints the selected stamp instead of selecting cells.
+    pub stamp_tool: Option<StampTool>,
+    /// Number of cells from the cursor to the edge of the square brush.
+    pub brush_radius: u32,
+    /// Probability of each cell under the brush to be stamped by [`StampTool::Spray`].
+    pub spray_fraction: f64,
+    /// Start cell of the dragged rectangle or the last cell painted by the brush.
+    pub stamp_drag: Option<usize>,
+
+    /// Replicator phase transition detected by the metrics recorder of the world.
+    pub transition: Option<PhaseTransition>,
+    /// Pause the simulation when the transition is detected.
+    pub pause_on_transition: bool,
+
+    /// Writes the metric samples of every run to a file.
+    pub metrics_sink: Option<MetricsSink>,
+
+    /// Show the heatmap of opcode frequencies over the recent metric samples.
+    pub show_opcode_heatmap: bool,
+    /// Show scrolling line charts of the recent metric samples.
+    pub show_metric_charts: bool,
+}
+
+impl AppState {
+    pub const DEFAULT_METRICS_INTERVAL: u64 = 256;
+
+    pub fn new(world_size: AreaSize, vm_config: VmConfig) -> Self {
+        let world = World::with_config(world_size, vm_config);
+
+        let image_size = world.get_image_size();
+        let mut world_canvas =
+            Image::gen_image_color(image_size.width as u16, image_size.height as u16, BLACK);
+
+        world.draw_to_image(&mut world_canvas);
+
+        let world_texture = Texture2D::from_image(&world_canvas);
+        world_texture.set_filter(FilterMode::Nearest);
+
+        // built-in replicators supported by the config are always available as stamps
+        let stamps = REPLICATORS
+            .iter()
+            .filter_map(|replicator| replicator.stamp(&world.vm_config).ok())
+            .collect();
+
+        Self {
+            world,
+            world_canvas,
+            world_texture,
+            ticks_per_update: 1,
+            is_paused: true,
+            selected_cell: None,
+            inspected_address: None,
+            breakpoints: Vec::new(),
+            triggered_breakpoint: None,
+            view_zoom: 1.0,
+            view_offset: Vec2::ZERO,
+            seeded_replicators: Vec::new(),
+            stamps,
+            selected_stamp: 0,
+            stamp_tool: None,
+            brush_radius: 0,
+            spray_fraction: 0.25,
+            stamp_drag: None,
+            transition: None,
+            pause_on_transition: false,
+            metrics_sink: None,
+            show_opcode_heatmap: false,
+            show_metric_charts: false,
+        }
+    }
+
+    /// Start sampling metrics if they are not sampled yet. Sampling is opt-in because it
+    /// compresses the whole world every interval and keeps every sample.
+    pub fn enable_metrics(&mut self) -> &mut MetricsRecorder {
+        self.world.metrics.get_or_insert_with(|| {
+            MetricsRecorder::new(Self::DEFAULT_METRICS_INTERVAL)
+                .with_detector(TransitionDetector::default())
+        })
+    }
+
+    pub fn reset(&mut self) {
+        let metrics = self.world.metrics.as_ref().map(MetricsRecorder::restarted);
+        let lineage = self
+            .world
+            .lineage
+            .as_ref()
+            .map(|lineage| LineageTracker::new(lineage.copy_threshold));
+        let tracks_provenance = self.world.tracks_provenance();
+
+        self.world = World::with_config(self.world.size, self.world.vm_config);
+        self.world.metrics = metrics;
+        if let Some(lineage) = lineage {
+            self.world.track_lineage(lineage);
+        }
+        if tracks_provenance {
+            // the world has the same size and memory size as the one tracking it
+            self.world
+                .track_provenance()
+                .expect("provenance tokens fit into the world");
+        }
+        for &(replicator, fraction) in &self.seeded_replicators {
+            self.world
+                .seed_replicator(replicator, fraction, &mut ::rand::thread_rng())
+                .expect("replicator was seeded with the same config");
+        }
+        self.transition = None;
+
+        if let Some(sink) = &mut self.metrics_sink {
+            let run_id = MetricsSink::default_run_id(self.world.seed);
+            sink.restart(run_id, &self.world.vm_config)
+                .expect("default run id is valid");
+        }
+    }
+
+    pub fn on_frame(&mut self) {
+        self.draw_world();
+        self.draw_debug_text();
+        self.draw_inspector();
+        self.draw_opcode_heatmap();
+        self.draw_metric_charts();
+        self.draw_stamp_preview();
+
+        self.handle_stamping();
+        self.handle_cell_selection();
+        self.handle_breakpoints();
+        self.handle_zoom();
+        self.handle_reset();
+        self.handle_pause_switch();
+        self.handle_lineage_export();
+        self.handle_opcode_heatmap_switch();
+        self.handle_metric_charts_switch();
+        self.handle_tick_speed_selection();
+        self.handle_ticks();
+    }
+
+    pub fn handle_reset(&mut self) {
+        if is_key_pressed(KeyCode::R) {
+            self.reset();
+            self.update_texture();
+        }
+    }
+
+    pub fn handle_ticks(&mut self) {
+        if !self.is_paused || is_key_pressed(KeyCode::Space) {
+            for _ in 0..self.ticks_per_update {
+                for breakpoint in &mut self.breakpoints {
+                    breakpoint.prepare(&mut self.world);
+                }
+
+                self.world.tick();
+
+                if self.check_breakpoints() || self.check_transition() {
+                    break;
+                }
+            }
+
+            self.write_metrics();
+        }
+    }
+
+    /// Write new metric samples to the sink, the sink is dropped if writing fails.
+    fn write_metrics(&mut self) {
+        let (Some(sink), Some(metrics)) = (&mut self.metrics_sink, &self.world.metrics) else {
+            return;
+        };
+
+        if let Err(err) = sink.write_new(&metrics.samples) {
+            eprintln!("Failed to write metrics, stopped writing: {err}");
+            self.metrics_sink = None;
+        }
+    }
+
+    pub fn handle_pause_switch(&mut self) {
+        if is_key_pressed(KeyCode::P) {
+            self.is_paused = !self.is_paused;
+        }
+        if is_key_pressed(KeyCode::T) {
+            self.pause_on_transition = !self.pause_on_transition;
+            // the takeover is detected from the metric samples
+            if self.pause_on_transition {
+                self.enable_metrics();
+            }
+        }
+    }
+
+    pub fn handle_lineage_export(&mut self) {
+        if is_key_pressed(KeyCode::L) {
+            self.save_lineage();
+        }
+    }
+
+    /// Save the phylogeny of copied genomes in Newick format.
+    fn save_lineage(&self) {
+        let Some(lineage) = &self.world.lineage else {
+            eprintln!("Lineage is not tracked, pass --lineage to track it");
+            return;
+        };
+
+        let path = format!("lineage-tick-{}.nwk", self.world.tick_count);
+        match std::fs::write(&path, lineage.to_newick()) {
+            Ok(()) => println!("Saved {} genomes to {path}", lineage.genomes.len()),
+            Err(err) => eprintln!("Failed to save lineage to {path}: {err}"),
+        }
+    }
+
+    /// Remember the detected phase transition. Returns true if it paused the simulation.
+    pub fn check_transition(&mut self) -> bool {
+        let Some(transition) = self
+            .world
+            .metrics
+            .as_mut()
+            .and_then(|metrics| metrics.take_events().pop())
+        else {
+            return false;
+        };
+
+        println!("{transition}");
+        self.transition = Some(transition);
+        if self.pause_on_transition {
+            self.is_paused = true;
+        }
+
+        self.pause_on_transition
+    }
+
+    pub fn update_texture(&mut self) {
+        self.world.draw_to_image(&mut self.world_canvas);
+        self.world_texture.update(&self.world_canvas);
+    }
+
+    pub fn draw_world(&mut self) {
+        if !self.is_paused {
+            self.update_texture();
+        }
+
+        let scale = self.get_world_scale();
+        let scaled_width = self.world_canvas.width as f32 * scale;
+        let scaled_height = self.world_canvas.height as f32 * scale;
+        let pos = self.canvas_to_screen(Vec2::ZERO);
+
+        draw_texture_ex(
+            &self.world_texture,
+            pos.x,
+            pos.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: vec2(scaled_width, scaled_height).into(),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Returns the size of a world canvas pixel on the screen.
+    pub fn get_world_scale(&self) -> f32 {
+        let width = screen_width();
+        let height = screen_height();
+
+        let mut pixel_size = height / self.world_canvas.height as f32;
+        if self.world_canvas.width as f32 * pixel_size > width {
+            pixel_size = width / self.world_canvas.width as f32;
+        }
+
+        pixel_size * self.view_zoom
+    }
+
+    pub fn screen_to_canvas(&self, screen_pos: Vec2) -> Vec2 {
+        screen_pos / self.get_world_scale() + self.view_offset
+    }
+
+    pub fn canvas_to_screen(&self, canvas_pos: Vec2) -> Vec2 {
+        (canvas_pos - self.view_offset) * self.get_world_scale()
+    }
+
+    /// Mouse wheel zooms the world around the cursor.
+    pub fn handle_zoom(&mut self) {
+        let (_, wheel) = mouse_wheel();
+        if wheel == 0.0 {
+            return;
+        }
+
+        let cursor = Vec2::from(mouse_position());
+        let cursor_on_canvas = self.screen_to_canvas(cursor);
+
+        let factor = if wheel > 0.0 { 1.25 } else { 0.8 };
+        self.view_zoom = (self.view_zoom * factor).max(1.0);
+        self.view_offset = cursor_on_canvas - cursor / self.get_world_scale();
+        self.clamp_view();
+    }
+
+    /// Zoom in if needed and move the view so the cell is in the center of the screen.
+    pub fn center_on_cell(&mut self, index: usize) {
+        const MIN_ZOOM: f32 = 4.0;
+
+        let cell_size = self.world.vm_config.cell_canvas_size();
+        let pos = self.world.size.index_to_coords(index) * cell_size;
+        let cell_center = vec2(
+            pos.x as f32 + cell_size.width as f32 / 2.0,
+            pos.y as f32 + cell_size.height as f32 / 2.0,
+        );
+
+        self.view_zoom = self.view_zoom.max(MIN_ZOOM);
+        let screen_size = vec2(screen_width(), screen_height()) / self.get_world_scale();
+        self.view_offset = cell_center - screen_size / 2.0;
+        self.clamp_view();
+    }
+
+    /// Keep the world on the screen.
+    fn clamp_view(&mut self) {
+        let canvas_size = vec2(
+            self.world_canvas.width as f32,
+            self.world_canvas.height as f32,
+        );
+        let screen_size = vec2(screen_width(), screen_height()) / self.get_world_scale();
+        let max_offset = (canvas_size - screen_size).max(Vec2::ZERO);
+
+        self.view_offset = self.view_offset.clamp(Vec2::ZERO, max_offset);
+    }
+
+    pub fn handle_tick_speed_selection(&mut self) {
+        if is_key_pressed(KeyCode::Up) {
+            self.ticks_per_update += 1;
+        }
+
+        if is_key_pressed(KeyCode::Down) {
+            self.ticks_per_update = self.ticks_per_update.saturating_sub(1).max(1);
+        }
+    }
+
+    pub fn draw_debug_text(&self) {
+        let x = 10.0;
+
+        let mut y = 00.0;
+        macro_rules! draw_text {
+            ($($arg:tt)*) => {{
+                y += 20.0;
+                draw_text_with_shadow(&format!($($arg)*), x, y, WHITE);
+            }};
+        }
+
+        let fps = get_fps();
+        draw_text!("FPS: {fps}");
+
+        draw_text!(
+            "Ticks per update (up/down to change): {}",
+            self.ticks_per_update
+        );
+
+        if let Some(sample) = self
+            .world
+            .metrics
+            .as_ref()
+            .and_then(MetricsRecorder::last_sample)
+        {
+            let entropy = &sample.entropy;
+            draw_text!(
+                "High-order entropy: {:.3} (shannon {:.3} - compressed {:.3}, tick {})",
+                entropy.high_order(),
+                entropy.shannon_entropy,
+                entropy.compressed_size,
+                sample.tick
+            );
+
+            let census = &sample.census;
+            let dominant = census.top.first().map_or(0, |genome| genome.count);
+            draw_text!(
+                "Genomes: {} unique, most abundant {dominant} ({:.1}%), simpson {:.3}, shannon {:.3}",
+                census.unique_genomes,
+                dominant as f64 * 100.0 / census.population.max(1) as f64,
+                census.simpson_diversity,
+                census.shannon_diversity
+            );
+
+            if let Some(tokens) = &sample.tokens {
+                let memory_size = self.world.vm_config.memory_size;
+                let dominant = tokens.top.first().map_or_else(
+                    || "none".to_owned(),
+                    |token| {
+                        let (cell, address) = token.origin(memory_size);
+                        format!("#{cell}:{address:#04x} x{}", token.count)
+                    },
+                );
+                draw_text!(
+                    "Tracer tokens: {} of {} surviving ({:.1}%), dominant {dominant}",
+                    tokens.surviving,
+                    tokens.original,
+                    tokens.surviving as f64 * 100.0 / tokens.original.max(1) as f64
+                );
+            }
+
+            if let Some(spatial) = &sample.spatial {
+                draw_text!(
+                    "Domains: {}, largest {}, boundary {}, correlation length {}",
+                    spatial.domains,
+                    spatial.largest_domain,
+                    spatial.boundary_length,
+                    spatial.correlation_length().map_or_else(
+                        || format!(">{}", spatial.autocorrelation.len()),
+                        |length| length.to_string()
+                    )
+                );
+            }
+        } else if self.world.metrics.is_none() {
+            draw_text!("Metrics: off, pass --metrics-interval <ticks> to sample them");
+        }
+
+        match &self.transition {
+            Some(transition) => draw_text!("{transition}"),
+            None if self.pause_on_transition => {
+                draw_text!("Pause on replicator takeover: on (T to toggle)")
+            }
+            None => draw_text!("Pause on replicator takeover: off (T to toggle)"),
+        }
+
+        if let Some(lineage) = &self.world.lineage {
+            draw_text!(
+                "Lineage: {} copied genomes (L to save)",
+                lineage.genomes.len()
+            );
+        }
+
+        for breakpoint in &self.breakpoints {
+            draw_text!("Breakpoint: {breakpoint}");
+        }
+
+        if let Some(breakpoint) = &self.triggered_breakpoint {
+            draw_text!("Paused by: {breakpoint}");
+        }
+
+        if let Some(tool) = self.stamp_tool {
+            match self.stamps.get(self.selected_stamp) {
+                Some(stamp) => draw_text!(
+                    "Stamp: {} ({tool:?}, brush {}, spray {:.0}%)",
+                    stamp.name,
+                    self.brush_radius * 2 + 1,
+                    self.spray_fraction * 100.0
+                ),
+                None => draw_text!("Stamp: none, pass program files as arguments or press C"),
+            }
+        }
+    }
+}
+
+/// Draw text readable on top of the world.
+pub fn draw_text_with_shadow(text: &str, x: f32, y: f32, color: Color) {
+    let text_size = 16.0;
+    let shadow_color = BLACK;
+    let shadow_offset = 1.0;
+
+    draw_text(
+        text,
+        x + shadow_offset,
+        y + shadow_offset,
+        text_size,
+        shadow_color,
+    );
+    draw_text(
+        text,
+        x - shadow_offset,
+        y + shadow_offset,
+        text_size,
+        shadow_color,
+    );
+    draw_text(
+        text,
+        x + shadow_offset,
+        y - shadow_offset,
+        text_size,
+        shadow_color,
+    );
+    draw_text(
+        text,
+        x - shadow_offset,
+        y - shadow_offset,
+        text_size,
+        shadow_color,
+    );
+    draw_text(text, x, y, text_size, color);
+}
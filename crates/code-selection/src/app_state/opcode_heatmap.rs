@@ -0,0 +1,98 @@
+use crate::*;
+use macroquad::prelude::*;
+
+impl AppState {
+    const HEATMAP_WIDTH: f32 = 420.0;
+    const HEATMAP_LABELS_WIDTH: f32 = 80.0;
+    const HEATMAP_COLUMN_WIDTH: f32 = 4.0;
+    const HEATMAP_MEMORY_ROW_HEIGHT: f32 = 0.75;
+    const HEATMAP_EXECUTED_ROW_HEIGHT: f32 = 10.0;
+    const HEATMAP_TITLE_HEIGHT: f32 = 20.0;
+
+    pub fn handle_opcode_heatmap_switch(&mut self) {
+        if is_key_pressed(KeyCode::F) {
+            self.show_opcode_heatmap = !self.show_opcode_heatmap;
+        }
+    }
+
+    /// Draw opcode frequencies in the memory and executed instructions of the recent samples,
+    /// one column per sample with the newest on the right.
+    pub fn draw_opcode_heatmap(&self) {
+        let Some(metrics) = self
+            .world
+            .metrics
+            .as_ref()
+            .filter(|_| self.show_opcode_heatmap)
+        else {
+            return;
+        };
+
+        let memory_height = 256.0 * Self::HEATMAP_MEMORY_ROW_HEIGHT;
+        let executed_height = Instruction::VARIANT_COUNT as f32 * Self::HEATMAP_EXECUTED_ROW_HEIGHT;
+        let height = memory_height + executed_height + 2.0 * Self::HEATMAP_TITLE_HEIGHT + 10.0;
+
+        let x = 10.0;
+        let y = screen_height() - height - 10.0;
+        draw_rectangle(
+            x,
+            y,
+            Self::HEATMAP_WIDTH,
+            height,
+            Color::new(0.0, 0.0, 0.0, 0.8),
+        );
+
+        let columns = ((Self::HEATMAP_WIDTH - Self::HEATMAP_LABELS_WIDTH)
+            / Self::HEATMAP_COLUMN_WIDTH) as usize;
+        let samples = &metrics.samples[metrics.samples.len().saturating_sub(columns)..];
+        let columns_x = x + Self::HEATMAP_LABELS_WIDTH;
+
+        let memory_y = y + Self::HEATMAP_TITLE_HEIGHT;
+        draw_text_with_shadow("Opcodes in memory", x + 5.0, memory_y - 5.0, WHITE);
+        draw_text_with_shadow("0x00", x + 5.0, memory_y + 12.0, GRAY);
+        draw_text_with_shadow("0xff", x + 5.0, memory_y + memory_height, GRAY);
+        for (column, sample) in samples.iter().enumerate() {
+            draw_heatmap_column(
+                &sample.opcodes.in_memory,
+                columns_x + column as f32 * Self::HEATMAP_COLUMN_WIDTH,
+                memory_y,
+                Self::HEATMAP_MEMORY_ROW_HEIGHT,
+            );
+        }
+
+        let executed_y = memory_y + memory_height + 5.0 + Self::HEATMAP_TITLE_HEIGHT;
+        draw_text_with_shadow("Executed instructions", x + 5.0, executed_y - 5.0, WHITE);
+        for (row, name) in Instruction::VARIANT_NAMES.iter().enumerate() {
+            let row_y = executed_y + (row + 1) as f32 * Self::HEATMAP_EXECUTED_ROW_HEIGHT;
+            draw_text(name, x + 5.0, row_y - 1.0, 12.0, GRAY);
+        }
+        for (column, sample) in samples.iter().enumerate() {
+            draw_heatmap_column(
+                &sample.stats.executed,
+                columns_x + column as f32 * Self::HEATMAP_COLUMN_WIDTH,
+                executed_y,
+                Self::HEATMAP_EXECUTED_ROW_HEIGHT,
+            );
+        }
+    }
+}
+
+/// Draw counts top to bottom, the brightness is relative to the largest count of the column.
+fn draw_heatmap_column(counts: &[u64], x: f32, y: f32, row_height: f32) {
+    let max = counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    for (row, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+
+        // square root keeps rare opcodes visible next to the dominant ones
+        let intensity = (count as f32 / max).sqrt();
+        draw_rectangle(
+            x,
+            y + row as f32 * row_height,
+            AppState::HEATMAP_COLUMN_WIDTH,
+            row_height,
+            Color::new(intensity, intensity * 0.8, 1.0 - intensity, 1.0),
+        );
+    }
+}
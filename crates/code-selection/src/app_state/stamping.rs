@@ -0,0 +1,211 @@
+use crate::*;
+use macroquad::prelude::*;
+use std::path::Path;
+
+impl AppState {
+    const MAX_BRUSH_RADIUS: u32 = 16;
+    const SPRAY_FRACTION_STEP: f64 = 0.05;
+
+    /// Load the stamp from a file and make it the current one.
+    pub fn load_stamp(&mut self, path: &Path) -> Result<(), String> {
+        let stamp = Stamp::load(path, &self.world.vm_config)?;
+
+        self.stamps.push(stamp);
+        self.selected_stamp = self.stamps.len() - 1;
+
+        Ok(())
+    }
+
+    /// Seed the world with a built-in replicator, `name=fraction` sets the fraction of
+    /// replaced cells. The world is seeded again when it's reset.
+    pub fn seed_replicator(&mut self, value: &str) -> Result<(), String> {
+        let (replicator, fraction) = Replicator::parse_seeding(value)?;
+
+        self.world
+            .seed_replicator(replicator, fraction, &mut ::rand::thread_rng())?;
+        self.seeded_replicators.push((replicator, fraction));
+        self.update_texture();
+
+        Ok(())
+    }
+
+    /// - `1` fill, `2` spray, `3` rectangle tool, `` ` `` back to cell selection
+    /// - `Tab` switches to the next stamp
+    /// - `C` copies the cell selected in the inspector as a new stamp, `S` saves it to a file
+    /// - `[`/`]` change the brush size, `-`/`=` change the spray fraction
+    pub fn handle_stamping(&mut self) {
+        if is_key_pressed(KeyCode::Key1) {
+            self.stamp_tool = Some(StampTool::Fill);
+        }
+        if is_key_pressed(KeyCode::Key2) {
+            self.stamp_tool = Some(StampTool::Spray);
+        }
+        if is_key_pressed(KeyCode::Key3) {
+            self.stamp_tool = Some(StampTool::Rectangle);
+        }
+        if is_key_pressed(KeyCode::GraveAccent) {
+            self.stamp_tool = None;
+        }
+
+        if is_key_pressed(KeyCode::Tab) && !self.stamps.is_empty() {
+            self.selected_stamp = (self.selected_stamp + 1) % self.stamps.len();
+        }
+
+        if is_key_pressed(KeyCode::LeftBracket) {
+            self.brush_radius = self.brush_radius.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::RightBracket) {
+            self.brush_radius = (self.brush_radius + 1).min(Self::MAX_BRUSH_RADIUS);
+        }
+        if is_key_pressed(KeyCode::Minus) {
+            self.spray_fraction = (self.spray_fraction - Self::SPRAY_FRACTION_STEP).max(0.0);
+        }
+        if is_key_pressed(KeyCode::Equal) {
+            self.spray_fraction = (self.spray_fraction + Self::SPRAY_FRACTION_STEP).min(1.0);
+        }
+
+        if let Some(index) = self.selected_cell {
+            if is_key_pressed(KeyCode::C) {
+                self.stamps.push(Stamp {
+                    name: format!("cell #{index} at tick {}", self.world.tick_count),
+                    cell: self.world.cells[index].clone(),
+                });
+                self.selected_stamp = self.stamps.len() - 1;
+            }
+
+            if is_key_pressed(KeyCode::S) {
+                let path = format!("cell-{index}-tick-{}.cell", self.world.tick_count);
+                let bytes = self.world.cells[index].to_bytes(&self.world.vm_config);
+
+                match std::fs::write(&path, bytes) {
+                    Ok(()) => println!("Saved cell #{index} to {path}"),
+                    Err(err) => eprintln!("Failed to save cell #{index} to {path}: {err}"),
+                }
+            }
+        }
+
+        self.handle_stamp_painting();
+    }
+
+    fn handle_stamp_painting(&mut self) {
+        let Some(tool) = self.stamp_tool else {
+            return;
+        };
+        if self.selected_stamp >= self.stamps.len() {
+            return;
+        }
+
+        let (x, y) = mouse_position();
+        let is_over_inspector =
+            self.selected_cell.is_some() && x >= screen_width() - Self::INSPECTOR_WIDTH;
+        let cursor_cell = self
+            .get_cell_at_screen_position(x, y)
+            .filter(|_| !is_over_inspector);
+
+        let corners = match tool {
+            StampTool::Fill | StampTool::Spray => {
+                if !is_mouse_button_down(MouseButton::Left) {
+                    self.stamp_drag = None;
+                    return;
+                }
+
+                // stamp once per cell the cursor moves over
+                let Some(cell) = cursor_cell.filter(|&cell| self.stamp_drag != Some(cell)) else {
+                    return;
+                };
+                self.stamp_drag = Some(cell);
+
+                self.get_brush_corners(cell)
+            }
+            StampTool::Rectangle => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    self.stamp_drag = cursor_cell;
+                }
+                if !is_mouse_button_released(MouseButton::Left) {
+                    return;
+                }
+
+                let (Some(start), Some(end)) = (self.stamp_drag.take(), cursor_cell) else {
+                    return;
+                };
+
+                (
+                    self.world.size.index_to_coords(start),
+                    self.world.size.index_to_coords(end),
+                )
+            }
+        };
+
+        let fraction = match tool {
+            StampTool::Spray => self.spray_fraction,
+            StampTool::Fill | StampTool::Rectangle => 1.0,
+        };
+
+        self.world.stamp_area(
+            corners.0,
+            corners.1,
+            &self.stamps[self.selected_stamp].cell,
+            fraction,
+            &mut ::rand::thread_rng(),
+        );
+        self.update_texture();
+    }
+
+    /// Corners of the square brush centered at the cell.
+    fn get_brush_corners(&self, cell: usize) -> (RelativePosition, RelativePosition) {
+        let pos = self.world.size.index_to_coords(cell);
+        let radius = self.brush_radius;
+
+        (
+            RelativePosition::new(pos.x.saturating_sub(radius), pos.y.saturating_sub(radius)),
+            RelativePosition::new(pos.x + radius, pos.y + radius),
+        )
+    }
+
+    /// Outline the cells the current tool would stamp.
+    pub fn draw_stamp_preview(&self) {
+        let Some(tool) = self.stamp_tool else {
+            return;
+        };
+
+        let (x, y) = mouse_position();
+        let Some(cursor_cell) = self.get_cell_at_screen_position(x, y) else {
+            return;
+        };
+
+        let (corner0, corner1) = match (tool, self.stamp_drag) {
+            (StampTool::Rectangle, Some(start)) => (
+                self.world.size.index_to_coords(start),
+                self.world.size.index_to_coords(cursor_cell),
+            ),
+            (StampTool::Rectangle, None) => {
+                let pos = self.world.size.index_to_coords(cursor_cell);
+                (pos, pos)
+            }
+            (StampTool::Fill | StampTool::Spray, _) => self.get_brush_corners(cursor_cell),
+        };
+
+        let max = RelativePosition::new(
+            corner0
+                .x
+                .max(corner1.x)
+                .min(self.world.size.width as u32 - 1),
+            corner0
+                .y
+                .max(corner1.y)
+                .min(self.world.size.height as u32 - 1),
+        );
+        let min = RelativePosition::new(corner0.x.min(corner1.x), corner0.y.min(corner1.y));
+
+        let min_rect = self.get_cell_screen_rect(self.world.size.coords_to_index(min));
+        let max_rect = self.get_cell_screen_rect(self.world.size.coords_to_index(max));
+        draw_rectangle_lines(
+            min_rect.x,
+            min_rect.y,
+            max_rect.right() - min_rect.x,
+            max_rect.bottom() - min_rect.y,
+            2.0,
+            MAGENTA,
+        );
+    }
+}
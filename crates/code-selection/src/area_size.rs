@@ -47,6 +47,11 @@ impl AreaSize {
         y * self.width + x
     }
 
+    #[inline(always)]
+    pub fn contains(&self, position: RelativePosition) -> bool {
+        (position.x as usize) < self.width && (position.y as usize) < self.height
+    }
+
     #[inline(always)]
     pub fn is_intersects_with_rect(self, rect_pos: Position, rect_size: Self) -> bool {
         if rect_pos.x >= self.width as i32 || rect_pos.y >= self.height as i32 {
@@ -0,0 +1,433 @@
+//! Run the world without a window and print metrics over time.
+//!
+//! ```sh
+//! cargo run --release --bin headless -- --ticks 100000 --metrics-interval 1000
+//! ```
+
+use code_selection::*;
+use rand::{rngs::SmallRng, SeedableRng};
+use std::io::Write;
+
+const USAGE: &str = "\
+Usage: headless [options]
+
+Options:
+    --ticks <n>               number of world ticks to run [default: 10000]
+    --size <n>                width and height of the world, must be even [default: 128]
+    --seed <n>                seed of the world [default: random]
+    --metrics-interval <n>    ticks between metric samples [default: 256]
+    --metrics-out <path>      write every sample to a .csv or .jsonl file
+    --metrics-columns <a,b>   write only the named metrics, e.g. tick,unique_genomes,stack_traps
+                              [default: all]
+    --run-id <id>             run id of the written samples [default: <seed>-<unix time>]
+    --census-top <n>          number of the most abundant genomes to print [default: 5]
+    --census-registers        count cells with equal memory but different registers as
+                              different genomes
+    --spatial                 sample domains of similar genomes and spatial autocorrelation
+    --spatial-similarity <f>  share of equal bytes joining adjacent cells into a domain
+                              [default: 0.9]
+    --spatial-distance <n>    largest distance of the autocorrelation [default: 8]
+    --stop-on-transition      stop when replicators take over
+    --transition-drop <bits>  drop of the compressed size in bits per byte over the last
+                              8 samples flagging the takeover, 0 disables [default: 1]
+    --transition-share <f>    share of cells with the same genome flagging the takeover,
+                              0 disables [default: 0.1]
+    --opcodes <path>          write opcode frequencies in the memory and executed
+                              instructions of every sample as CSV to the path
+    --provenance              record the origin cell, tick and tracer token of every byte,
+                              print how far the most widespread origins got and how many
+                              tokens survive at every sample
+    --lineage <path>          track which cells copied which and write the phylogeny in
+                              Newick format to the path at the end of the run
+    --copy-threshold <f>      share of the neighbor memory main must overwrite with its
+                              own bytes to make the neighbor a copy, from 0 to 1
+                              [default: 0.5]
+    --trace <path>            record every instruction of pairs of the traced cells and
+                              write the most recent ones as JSON Lines to the path
+    --trace-cell <index>      world index of a traced cell, may be repeated
+    --trace-capacity <n>      number of the most recent instructions written by --trace
+                              [default: 65536]
+    --replicator <name>[=<fraction>]
+                              seed a fraction of cells with a built-in replicator,
+                              may be repeated
+    --memory-size <n>         memory size of a single cell [default: 128]
+    --bits16                  use 16-bit registers
+    --revision <0..3>         opcode map revision [default: 0]
+    --stack <mode>            wrap, separate:<size> or trap:<size> [default: wrap]
+    --protection <mode>       drop, cycles:<n> or end, disabled if not set";
+
+struct Args {
+    ticks: u64,
+    size: usize,
+    seed: u64,
+    metrics_interval: u64,
+    metrics_out: Option<String>,
+    metrics_columns: Option<String>,
+    run_id: Option<String>,
+    census: CensusOptions,
+    spatial: Option<SpatialOptions>,
+    detector: TransitionDetector,
+    stop_on_transition: bool,
+    replicators: Vec<(&'static Replicator, f64)>,
+    opcodes: Option<String>,
+    provenance: bool,
+    lineage: Option<String>,
+    copy_threshold: f64,
+    trace: Option<String>,
+    trace_cells: Vec<u32>,
+    trace_capacity: usize,
+    config: VmConfig,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(args) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut world = World::with_seed(AreaSize::splat(args.size), args.config, args.seed);
+    let mut metrics = MetricsRecorder::new(args.metrics_interval)
+        .with_census(args.census)
+        .with_detector(args.detector);
+    metrics.spatial = args.spatial;
+    world.metrics = Some(metrics);
+
+    let mut rng = SmallRng::seed_from_u64(args.seed);
+    for &(replicator, fraction) in &args.replicators {
+        let seeded = world.seed_replicator(replicator, fraction, &mut rng)?;
+        println!("seeded {seeded} cells with {}", replicator.name);
+    }
+    if args.provenance {
+        world.track_provenance()?;
+    }
+    if args.lineage.is_some() {
+        world.track_lineage(LineageTracker::new(args.copy_threshold));
+    }
+    if args.trace.is_some() {
+        world.tracer =
+            Some(Tracer::new(args.trace_capacity).with_cells(args.trace_cells.iter().copied()));
+    }
+
+    let mut sink = match &args.metrics_out {
+        Some(path) => {
+            let run_id = args
+                .run_id
+                .clone()
+                .unwrap_or_else(|| MetricsSink::default_run_id(args.seed));
+            let mut sink = MetricsSink::create(path, run_id, &args.config)?;
+            if let Some(columns) = &args.metrics_columns {
+                sink = sink.with_columns(&columns.split(',').collect::<Vec<_>>())?;
+            }
+            println!("writing metrics of run {} to {path}", sink.run_id);
+            Some(sink)
+        }
+        None => None,
+    };
+
+    println!("seed {}", args.seed);
+    print_sample(
+        &world.sample_metrics(args.census, args.spatial),
+        &args.config,
+    );
+
+    let mut printed = 0;
+    while world.tick_count < args.ticks {
+        world.tick();
+
+        let samples = world
+            .metrics
+            .as_ref()
+            .map_or(&[][..], |metrics| &metrics.samples);
+        for sample in &samples[printed..] {
+            print_sample(sample, &args.config);
+        }
+        if args.provenance && samples.len() > printed {
+            print_provenance(&world, args.census.top);
+        }
+        if let Some(sink) = &mut sink {
+            sink.write_new(samples)
+                .map_err(|err| format!("failed to write metrics: {err}"))?;
+        }
+        printed = samples.len();
+
+        let events = world
+            .metrics
+            .as_mut()
+            .map(MetricsRecorder::take_events)
+            .unwrap_or_default();
+        for transition in &events {
+            println!("{transition}");
+
+            if let Some(tokens) = TokenCensus::new(&world.cells, args.census.top) {
+                println!(
+                    "dominant tokens after the transition: {}",
+                    format_tokens(&tokens, &args.config)
+                );
+            }
+        }
+        if args.stop_on_transition && !events.is_empty() {
+            break;
+        }
+    }
+
+    if let (Some(path), Some(metrics)) = (&args.opcodes, &world.metrics) {
+        let mut writer = std::io::BufWriter::new(
+            std::fs::File::create(path).map_err(|err| format!("failed to create {path}: {err}"))?,
+        );
+        write_opcodes_csv(&metrics.samples, &mut writer)
+            .and_then(|()| writer.flush())
+            .map_err(|err| format!("failed to write {path}: {err}"))?;
+        println!(
+            "wrote opcodes of {} samples to {path}",
+            metrics.samples.len()
+        );
+    }
+
+    if let (Some(path), Some(lineage)) = (&args.lineage, &world.lineage) {
+        std::fs::write(path, lineage.to_newick())
+            .map_err(|err| format!("failed to write {path}: {err}"))?;
+        println!("wrote {} genomes to {path}", lineage.genomes.len());
+    }
+
+    if let (Some(path), Some(tracer)) = (&args.trace, &world.tracer) {
+        let mut writer = std::io::BufWriter::new(
+            std::fs::File::create(path).map_err(|err| format!("failed to create {path}: {err}"))?,
+        );
+        tracer
+            .write_json_lines(&mut writer)
+            .and_then(|()| writer.flush())
+            .map_err(|err| format!("failed to write {path}: {err}"))?;
+        println!("wrote {} traced steps to {path}", tracer.entries.len());
+    }
+
+    Ok(())
+}
+
+fn print_provenance(world: &World, top: usize) {
+    for spread in world.provenance_spread().iter().take(top) {
+        let origin = world.size.index_to_coords(spread.origin as usize);
+        println!(
+            "tick {:>8}  origin #{} ({}, {})  bytes {}  cells {}  max distance {}  \
+             mean distance {:.2}  max speed {:.4}",
+            world.tick_count,
+            spread.origin,
+            origin.x,
+            origin.y,
+            spread.bytes,
+            spread.cells,
+            spread.max_distance,
+            spread.mean_distance,
+            spread.max_speed
+        );
+    }
+}
+
+fn format_tokens(tokens: &TokenCensus, config: &VmConfig) -> String {
+    let top = tokens
+        .top
+        .iter()
+        .map(|token| {
+            let (cell, address) = token.origin(config.memory_size);
+            format!("#{cell}:{address:#04x}x{}", token.count)
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        "surviving {} of {} ({} bytes)  top [{}]",
+        tokens.surviving,
+        tokens.original,
+        tokens.bytes,
+        top.join(", ")
+    )
+}
+
+fn print_sample(sample: &MetricsSample, config: &VmConfig) {
+    let entropy = &sample.entropy;
+    let census = &sample.census;
+    let top = census
+        .top
+        .iter()
+        .map(|genome| format!("{:016x}x{}", genome.hash, genome.count))
+        .collect::<Vec<_>>();
+
+    println!(
+        "tick {:>8}  shannon {:.4}  compressed {:.4}  high-order {:.4}  \
+         unique {}  simpson {:.4}  diversity {:.4}  top [{}]",
+        sample.tick,
+        entropy.shannon_entropy,
+        entropy.compressed_size,
+        entropy.high_order(),
+        census.unique_genomes,
+        census.simpson_diversity,
+        census.shannon_diversity,
+        top.join(", ")
+    );
+
+    if let Some(tokens) = &sample.tokens {
+        println!(
+            "tick {:>8}  tokens {}",
+            sample.tick,
+            format_tokens(tokens, config)
+        );
+    }
+
+    if let Some(spatial) = &sample.spatial {
+        let autocorrelation = spatial
+            .autocorrelation
+            .iter()
+            .map(|correlation| format!("{correlation:.3}"))
+            .collect::<Vec<_>>();
+
+        println!(
+            "tick {:>8}  domains {}  largest {}  weighted size {:.1}  boundary {}  \
+             autocorrelation [{}]",
+            sample.tick,
+            spatial.domains,
+            spatial.largest_domain,
+            spatial.weighted_domain_size,
+            spatial.boundary_length,
+            autocorrelation.join(", ")
+        );
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut result = Args {
+        ticks: 10000,
+        size: 128,
+        seed: rand::random(),
+        metrics_interval: 256,
+        metrics_out: None,
+        metrics_columns: None,
+        run_id: None,
+        census: CensusOptions::default(),
+        spatial: None,
+        detector: TransitionDetector::default(),
+        stop_on_transition: false,
+        replicators: Vec::new(),
+        opcodes: None,
+        provenance: false,
+        lineage: None,
+        copy_threshold: LineageTracker::DEFAULT_COPY_THRESHOLD,
+        trace: None,
+        trace_cells: Vec::new(),
+        trace_capacity: 65536,
+        config: VmConfig::default(),
+    };
+    let config = &mut result.config;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value of {arg}"));
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--ticks" => result.ticks = parse_number(&value()?)?,
+            "--size" => result.size = parse_number(&value()?)? as usize,
+            "--seed" => result.seed = parse_number(&value()?)?,
+            "--metrics-interval" => result.metrics_interval = parse_number(&value()?)?,
+            "--metrics-out" => result.metrics_out = Some(value()?),
+            "--metrics-columns" => result.metrics_columns = Some(value()?),
+            "--run-id" => result.run_id = Some(value()?),
+            "--census-top" => result.census.top = parse_number(&value()?)? as usize,
+            "--census-registers" => result.census.include_registers = true,
+            "--spatial" => {
+                result.spatial.get_or_insert_with(SpatialOptions::default);
+            }
+            "--spatial-similarity" => {
+                result
+                    .spatial
+                    .get_or_insert_with(SpatialOptions::default)
+                    .similarity_threshold = parse_fraction(&value()?)?;
+            }
+            "--spatial-distance" => {
+                result
+                    .spatial
+                    .get_or_insert_with(SpatialOptions::default)
+                    .max_distance = parse_number(&value()?)? as usize;
+            }
+            "--stop-on-transition" => result.stop_on_transition = true,
+            "--transition-drop" => {
+                let drop = parse_fraction(&value()?)?;
+                result.detector.compressed_drop = (drop > 0.0).then_some(drop);
+            }
+            "--transition-share" => {
+                let share = parse_fraction(&value()?)?;
+                result.detector.dominant_share = (share > 0.0).then_some(share);
+            }
+            "--opcodes" => result.opcodes = Some(value()?),
+            "--provenance" => result.provenance = true,
+            "--lineage" => result.lineage = Some(value()?),
+            "--copy-threshold" => result.copy_threshold = parse_share(&value()?)?,
+            "--trace" => result.trace = Some(value()?),
+            "--trace-cell" => result.trace_cells.push(parse_number(&value()?)? as u32),
+            "--trace-capacity" => result.trace_capacity = parse_number(&value()?)? as usize,
+            "--replicator" => result
+                .replicators
+                .push(Replicator::parse_seeding(&value()?)?),
+            "--memory-size" => config.memory_size = parse_number(&value()?)? as usize,
+            "--bits16" => config.address_mode = AddressMode::Bits16,
+            "--revision" => config.opcode_revision = value()?.parse()?,
+            "--stack" => config.stack_mode = value()?.parse()?,
+            "--protection" => config.write_protection = Some(value()?.parse()?),
+            arg => return Err(format!("unknown option {arg}")),
+        }
+    }
+
+    if result.size == 0 || !result.size.is_multiple_of(2) {
+        return Err(format!("world size {} must be even", result.size));
+    }
+    if result.trace.is_some() == result.trace_cells.is_empty() {
+        return Err("--trace and --trace-cell must be used together".to_owned());
+    }
+    if let Some(cell) = result
+        .trace_cells
+        .iter()
+        .find(|&&cell| cell as usize >= result.size * result.size)
+    {
+        return Err(format!("traced cell {cell} is outside of the world"));
+    }
+    if result.metrics_interval == 0 {
+        return Err("metrics interval must be positive".to_owned());
+    }
+    config.validate()?;
+
+    Ok(result)
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| *value >= 0.0)
+        .ok_or_else(|| format!("invalid non-negative number {value}"))
+}
+
+fn parse_share(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| (0.0..=1.0).contains(value))
+        .ok_or_else(|| format!("invalid fraction `{value}`, expected 0 to 1"))
+}
+
+fn parse_number(value: &str) -> Result<u64, String> {
+    let result = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    result.map_err(|err| format!("invalid number {value}: {err}"))
+}
@@ -0,0 +1,270 @@
+//! Run a single cell pair outside of the world.
+//!
+//! ```sh
+//! cargo run --bin vm-sandbox -- replicator.asm --cycles 512 --trace
+//! ```
+
+use code_selection::*;
+use std::{error::Error, fmt::Write as _, path::Path};
+
+const USAGE: &str = "\
+Usage: vm-sandbox <main> [neighbor] [options]
+
+Memory images ending with .asm are assembled, .cell files saved from the GUI are loaded
+with their registers, anything else is loaded as raw bytes. Images shorter than the memory
+are padded with zeros, missing neighbor is all zeros.
+
+Options:
+    --cycles <n>          number of instructions to run [default: 256]
+    --trace               print every executed instruction
+    --memory-size <n>     memory size of a single cell [default: 128]
+    --bits16              use 16-bit registers
+    --revision <0..3>     opcode map revision [default: 0]
+    --stack <mode>        wrap, separate:<size> or trap:<size> [default: wrap]
+    --protection <mode>   drop, cycles:<n> or end, disabled if not set
+    --seed <n>            seed of the pair rng [default: 0]
+    --reg <name>=<value>  initial register of the main cell, may be repeated
+    --cfg <path>          write the control-flow graph of the main cell from its pc to a
+                          Graphviz DOT file before running";
+
+struct Args {
+    main: String,
+    neighbor: Option<String>,
+    cycles: usize,
+    trace: bool,
+    seed: u64,
+    config: VmConfig,
+    registers: Vec<(Register, Word)>,
+    cfg: Option<String>,
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(args) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let config = args.config;
+
+    let mut main = load_cell(Some(&args.main), &config)?;
+    let mut neighbor = load_cell(args.neighbor.as_deref(), &config)?;
+    for &(register, value) in &args.registers {
+        main.registers[register as usize] = value & config.address_mode.word_mask();
+    }
+
+    if let Some(path) = &args.cfg {
+        write_cfg(&main, &config, Path::new(path))?;
+    }
+
+    let mut pair = CellPair::new(&mut main, &mut neighbor, config).with_seed(args.seed);
+    pair.cycles_to_run = args.cycles.saturating_sub(1);
+
+    let mut executed = 0;
+    while executed < args.cycles {
+        let instruction = args.trace.then(|| {
+            let memory = [pair.main.memory.as_slice(), &pair.neighbor.memory].concat();
+            DisassembledInstruction::decode(&memory, pair.get_reg_pc() as usize, &config)
+        });
+
+        let step = pair.step();
+        executed += 1;
+
+        if let Some(instruction) = instruction {
+            println!("{}", format_step(&instruction, &step));
+        }
+
+        if step.is_turn_over {
+            break;
+        }
+    }
+
+    let stats = pair.stats;
+    drop(pair);
+
+    println!(
+        "executed {executed} instructions, stack traps: {}",
+        stats.stack_traps
+    );
+    println!();
+    println!("registers: {}", format_registers(&main.registers));
+    println!();
+    println!("main memory:");
+    print!("{}", hex_dump(&main.memory, 0));
+    println!("neighbor memory:");
+    print!("{}", hex_dump(&neighbor.memory, config.memory_size));
+
+    Ok(())
+}
+
+fn write_cfg(cell: &CellState, config: &VmConfig, path: &Path) -> Result<(), Box<dyn Error>> {
+    let pc = cell.registers[CellState::REGISTER_PROGRAM_COUNTER] as usize % config.address_space();
+    if pc >= config.memory_size {
+        return Err(format!("pc {pc:#04x} is outside of the main memory").into());
+    }
+
+    let graph = ControlFlowGraph::build(&cell.memory, pc, config);
+    std::fs::write(path, graph.to_dot())
+        .map_err(|err| format!("failed to write {}: {err}", path.display()))?;
+
+    println!(
+        "control-flow graph: {} blocks, {} loops, {} unreachable bytes, {} instructions may write the neighbor",
+        graph.blocks.len(),
+        graph.loops.len(),
+        graph.unreachable.iter().map(|range| range.len()).sum::<usize>(),
+        graph.neighbor_writes().count()
+    );
+    println!();
+
+    Ok(())
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut images = Vec::new();
+    let mut cycles = 256;
+    let mut trace = false;
+    let mut seed = 0;
+    let mut config = VmConfig::default();
+    let mut registers = Vec::new();
+    let mut cfg = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value of {arg}"));
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            "--cycles" => cycles = parse_number(&value()?)? as usize,
+            "--trace" => trace = true,
+            "--memory-size" => config.memory_size = parse_number(&value()?)? as usize,
+            "--bits16" => config.address_mode = AddressMode::Bits16,
+            "--revision" => config.opcode_revision = value()?.parse()?,
+            "--stack" => config.stack_mode = value()?.parse()?,
+            "--protection" => config.write_protection = Some(value()?.parse()?),
+            "--seed" => seed = parse_number(&value()?)?,
+            "--cfg" => cfg = Some(value()?),
+            "--reg" => {
+                let value = value()?;
+                let (name, register_value) = value
+                    .split_once('=')
+                    .ok_or_else(|| format!("expected <name>=<value>, got {value}"))?;
+                let register = name.parse::<Register>()?;
+                registers.push((register, parse_number(register_value)? as Word));
+            }
+            arg if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => images.push(arg),
+        }
+    }
+
+    let mut images = images.into_iter();
+    let main = images.next().ok_or("missing main memory image")?;
+    let neighbor = images.next();
+    if let Some(image) = images.next() {
+        return Err(format!("unexpected argument {image}"));
+    }
+
+    config.validate()?;
+
+    Ok(Args {
+        main,
+        neighbor,
+        cycles,
+        trace,
+        seed,
+        config,
+        registers,
+        cfg,
+    })
+}
+
+fn parse_number(value: &str) -> Result<u64, String> {
+    let result = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    result.map_err(|err| format!("invalid number {value}: {err}"))
+}
+
+/// Load memory image of the cell, `None` creates a cell with zeroed memory.
+fn load_cell(path: Option<&str>, config: &VmConfig) -> Result<CellState, String> {
+    match path {
+        Some(path) => Stamp::load(Path::new(path), config).map(|stamp| stamp.cell),
+        None => Stamp::program_cell(Vec::new(), config),
+    }
+}
+
+fn format_registers(registers: &[Word; CellState::REGISTERS_COUNT]) -> String {
+    let mut result = String::new();
+    for (index, value) in registers.iter().enumerate() {
+        if index > 0 {
+            result.push(' ');
+        }
+        write!(result, "{}={value:#04x}", Register::from(index as u8)).unwrap();
+    }
+
+    result
+}
+
+fn format_step(instruction: &DisassembledInstruction, step: &StepRecord) -> String {
+    let mut result = format!(
+        "{:#06x}  {:02x}  {:<16}",
+        step.pc,
+        step.opcode,
+        instruction.to_string()
+    );
+
+    for change in &step.register_changes {
+        write!(
+            result,
+            " {}: {:#04x}->{:#04x}",
+            change.register, change.old, change.new
+        )
+        .unwrap();
+    }
+    for write in &step.memory_writes {
+        write!(
+            result,
+            " [{:#04x}]: {:#04x}->{:#04x}",
+            write.address, write.old, write.new
+        )
+        .unwrap();
+    }
+    for write in &step.stack_writes {
+        write!(
+            result,
+            " stack[{}]: {:#04x}->{:#04x}",
+            write.index, write.old, write.new
+        )
+        .unwrap();
+    }
+    if step.is_turn_over {
+        result.push_str(" (turn over)");
+    }
+
+    result
+}
+
+fn hex_dump(memory: &[u8], offset: usize) -> String {
+    let mut result = String::new();
+    for (line, chunk) in memory.chunks(16).enumerate() {
+        write!(result, "{:#06x}:", offset + line * 16).unwrap();
+        for byte in chunk {
+            write!(result, " {byte:02x}").unwrap();
+        }
+        result.push('\n');
+    }
+
+    result
+}
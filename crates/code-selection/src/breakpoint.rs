@@ -0,0 +1,198 @@
+use crate::*;
+use nohash_hasher::IntSet;
+
+/// Condition checked after every world tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// The cell executed the opcode while being main.
+    Opcode {
+        cell: usize,
+        opcode: u8,
+        /// Whether [`Breakpoint::prepare`] created the world tracer.
+        owns_tracer: bool,
+        /// Whether [`Breakpoint::prepare`] added the cell to the world tracer.
+        added_cell: bool,
+    },
+    /// Byte of the cell memory changed.
+    MemoryChange {
+        cell: usize,
+        address: usize,
+        last_value: u8,
+    },
+    /// Any main cell wrote into its neighbor.
+    NeighborWrite,
+    /// A cell which did not match at the moment the breakpoint was created got memory
+    /// with the given hash, see [`CellState::memory_hash`].
+    MemoryHash { hash: u64, matched: IntSet<usize> },
+}
+
+/// Number of steps kept by the tracer created for [`Breakpoint::Opcode`].
+const BREAKPOINT_TRACER_CAPACITY: usize = 4096;
+
+impl Breakpoint {
+    pub fn opcode(cell: usize, opcode: u8) -> Self {
+        Self::Opcode {
+            cell,
+            opcode,
+            owns_tracer: false,
+            added_cell: false,
+        }
+    }
+
+    pub fn memory_change(world: &World, cell: usize, address: usize) -> Self {
+        Self::MemoryChange {
+            cell,
+            address,
+            last_value: world.cells[cell].memory[address],
+        }
+    }
+
+    /// Break when any cell which does not match at the moment gets memory with the hash,
+    /// see [`CellState::memory_hash`].
+    pub fn memory_hash(world: &World, hash: u64) -> Self {
+        let matched = world
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.memory_hash() == hash)
+            .map(|(index, _)| index)
+            .collect();
+
+        Self::MemoryHash { hash, matched }
+    }
+
+    /// Prepare the world for the next tick.
+    pub fn prepare(&mut self, world: &mut World) {
+        match self {
+            Self::Opcode {
+                cell,
+                owns_tracer,
+                added_cell,
+                ..
+            } => {
+                let tracer = world.tracer.get_or_insert_with(|| {
+                    *owns_tracer = true;
+                    Tracer::new(BREAKPOINT_TRACER_CAPACITY)
+                });
+                *added_cell |= tracer.cells.insert(*cell as u32);
+            }
+            Self::MemoryChange {
+                cell,
+                address,
+                last_value,
+            } => {
+                *last_value = world.cells[*cell].memory[*address];
+            }
+            Self::NeighborWrite | Self::MemoryHash { .. } => {}
+        }
+    }
+
+    /// Undo what [`Breakpoint::prepare`] did to the world once the breakpoint is removed,
+    /// a tracer or traced cells set up by others stay. Breakpoints must be released in the
+    /// reverse order of their preparation, so the one which created the tracer is the last.
+    pub fn release(&self, world: &mut World) {
+        let Self::Opcode {
+            cell,
+            owns_tracer,
+            added_cell,
+            ..
+        } = *self
+        else {
+            return;
+        };
+
+        if let Some(tracer) = &mut world.tracer {
+            if added_cell {
+                tracer.cells.remove(&(cell as u32));
+            }
+
+            if owns_tracer && tracer.cells.is_empty() {
+                world.tracer = None;
+            }
+        }
+    }
+
+    /// Returns index of the cell which triggered the breakpoint during the last tick.
+    pub fn check(&mut self, world: &World) -> Option<usize> {
+        match self {
+            Self::Opcode { cell, opcode, .. } => {
+                let tick = world.tick_count.checked_sub(1)?;
+                let tracer = world.tracer.as_ref()?;
+
+                tracer
+                    .entries
+                    .iter()
+                    .rev()
+                    .take_while(|entry| entry.tick == tick)
+                    .any(|entry| entry.main_index as usize == *cell && entry.step.opcode == *opcode)
+                    .then_some(*cell)
+            }
+            Self::MemoryChange {
+                cell,
+                address,
+                last_value,
+            } => (world.cells[*cell].memory[*address] != *last_value).then_some(*cell),
+            Self::NeighborWrite => world
+                .last_tick_stats
+                .first_neighbor_writer
+                .map(|cell| cell as usize),
+            Self::MemoryHash { hash, matched } => {
+                let index = world.cells.iter().enumerate().position(|(index, cell)| {
+                    !matched.contains(&index) && cell.memory_hash() == *hash
+                })?;
+                matched.insert(index);
+
+                Some(index)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Opcode { cell, opcode, .. } => {
+                write!(f, "#{cell} executes {opcode:#04x}")
+            }
+            Self::MemoryChange { cell, address, .. } => {
+                write!(f, "#{cell} [{address:#04x}] changes")
+            }
+            Self::NeighborWrite => write!(f, "any write into neighbor"),
+            Self::MemoryHash { hash, .. } => write!(f, "memory hash {hash:016x}"),
+        }
+    }
+}
+
+#[test]
+fn test_release_opcode_breakpoint() {
+    let mut world = World::with_seed(AreaSize::splat(4), VmConfig::default(), 0);
+    let mut first = Breakpoint::opcode(1, 0);
+    let mut second = Breakpoint::opcode(2, 0);
+    first.prepare(&mut world);
+    second.prepare(&mut world);
+    first.prepare(&mut world);
+
+    second.release(&mut world);
+    let tracer = world.tracer.as_ref().unwrap();
+    assert!(tracer.cells.contains(&1));
+    assert!(!tracer.cells.contains(&2));
+
+    first.release(&mut world);
+    assert!(world.tracer.is_none());
+}
+
+#[test]
+fn test_release_keeps_user_tracer() {
+    let mut world = World::with_seed(AreaSize::splat(4), VmConfig::default(), 0);
+    world.tracer = Some(Tracer::new(16).with_cells([1]));
+    let mut traced = Breakpoint::opcode(1, 0);
+    let mut untraced = Breakpoint::opcode(2, 0);
+    traced.prepare(&mut world);
+    untraced.prepare(&mut world);
+
+    untraced.release(&mut world);
+    traced.release(&mut world);
+    let tracer = world.tracer.as_ref().unwrap();
+    assert_eq!(tracer.cells, [1].into_iter().collect());
+    assert_eq!(tracer.capacity, 16);
+}
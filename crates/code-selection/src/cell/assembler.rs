@@ -0,0 +1,385 @@
+use crate::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Error of [`assemble`] with the 1-based source line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// Assemble the program written in the syntax of [`Instruction::write_asm`].
+///
+/// Besides instructions the source may contain:
+/// - comments starting with `;`
+/// - labels `name:` which can be used instead of immediate values
+/// - `db 1, 0x02, label` to emit raw bytes and `dw ...` to emit register sized values
+///
+/// The symbol `memory_size` is predefined as the memory size of a cell, which is also the
+/// address of the neighbor memory.
+pub fn assemble(source: &str, config: &VmConfig) -> Result<Vec<u8>, AssembleError> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, parse_line(line)))
+        .collect::<Vec<_>>();
+
+    let word_bytes = config.address_mode.word_bytes() as usize;
+
+    // first pass: find label addresses
+    let mut labels = HashMap::from([("memory_size", config.memory_size)]);
+    let mut address = 0usize;
+    for (line_number, line) in &lines {
+        let line = line.as_ref().map_err(|message| AssembleError {
+            line: *line_number,
+            message: message.clone(),
+        })?;
+
+        if let Some(label) = line.label {
+            if labels.insert(label, address).is_some() {
+                return Err(AssembleError {
+                    line: *line_number,
+                    message: format!("duplicate label `{label}`"),
+                });
+            }
+        }
+
+        address += match &line.statement {
+            None => 0,
+            Some(Statement::Bytes(values)) => values.len(),
+            Some(Statement::Words(values)) => values.len() * word_bytes,
+            Some(Statement::Instruction { immediate, .. }) => {
+                1 + if immediate.is_some() { word_bytes } else { 0 }
+            }
+        };
+    }
+
+    // second pass: emit bytes
+    let mut result = Vec::with_capacity(address);
+    for (line_number, line) in lines {
+        let error = |message: String| AssembleError {
+            line: line_number,
+            message,
+        };
+        let resolve = |value: &Value| -> Result<Word, AssembleError> {
+            let value = match value {
+                Value::Number(value) => *value,
+                Value::Label(label) => *labels
+                    .get(label)
+                    .ok_or_else(|| error(format!("unknown label `{label}`")))?
+                    as u64,
+            };
+
+            if value > config.address_mode.word_mask() as u64 {
+                return Err(error(format!("value {value} does not fit into a register")));
+            }
+
+            Ok(value as Word)
+        };
+        let push_word = |result: &mut Vec<u8>, value: Word| {
+            result.extend_from_slice(&value.to_le_bytes()[..word_bytes]);
+        };
+
+        let Some(statement) = line.map_err(error)?.statement else {
+            continue;
+        };
+
+        match statement {
+            Statement::Bytes(values) => {
+                for value in &values {
+                    let value = resolve(value)?;
+                    let byte =
+                        u8::try_from(value).map_err(|_| error(format!("{value} is not a byte")))?;
+                    result.push(byte);
+                }
+            }
+            Statement::Words(values) => {
+                for value in &values {
+                    push_word(&mut result, resolve(value)?);
+                }
+            }
+            Statement::Instruction {
+                instruction,
+                immediate,
+            } => {
+                let opcode = instruction.encode(config.opcode_revision).ok_or_else(|| {
+                    error(format!(
+                        "`{instruction}` can not be encoded in {:?} opcode map",
+                        config.opcode_revision
+                    ))
+                })?;
+                result.push(opcode);
+
+                if let Some(immediate) = &immediate {
+                    push_word(&mut result, resolve(immediate)?);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+impl Instruction {
+    /// Returns the opcode of the instruction, `None` if the opcode map has no such instruction.
+    pub fn encode(&self, revision: OpcodeRevision) -> Option<u8> {
+        (0..=u8::MAX).find(|&opcode| Instruction::decode(opcode, revision) == *self)
+    }
+}
+
+struct Line<'a> {
+    label: Option<&'a str>,
+    statement: Option<Statement<'a>>,
+}
+
+enum Statement<'a> {
+    Instruction {
+        instruction: Instruction,
+        immediate: Option<Value<'a>>,
+    },
+    Bytes(Vec<Value<'a>>),
+    Words(Vec<Value<'a>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value<'a> {
+    Number(u64),
+    Label(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand<'a> {
+    Reg(Register),
+    AtReg(Register),
+    Value(Value<'a>),
+}
+
+fn parse_line(line: &str) -> Result<Line<'_>, String> {
+    let mut line = line.split(';').next().unwrap_or_default().trim();
+
+    let mut label = None;
+    if let Some((name, rest)) = line.split_once(':') {
+        let name = name.trim();
+        if !is_identifier(name) {
+            return Err(format!("invalid label `{name}`"));
+        }
+
+        label = Some(name);
+        line = rest.trim();
+    }
+
+    if line.is_empty() {
+        return Ok(Line {
+            label,
+            statement: None,
+        });
+    }
+
+    let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let operands = operands
+        .split(',')
+        .map(str::trim)
+        .filter(|operand| !operand.is_empty())
+        .map(parse_operand)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let statement = match mnemonic.to_ascii_lowercase().as_str() {
+        "db" => Statement::Bytes(values(&operands)?),
+        "dw" => Statement::Words(values(&operands)?),
+        mnemonic => parse_instruction(mnemonic, &operands)?,
+    };
+
+    Ok(Line {
+        label,
+        statement: Some(statement),
+    })
+}
+
+fn values<'a>(operands: &[Operand<'a>]) -> Result<Vec<Value<'a>>, String> {
+    operands
+        .iter()
+        .map(|operand| match operand {
+            Operand::Value(value) => Ok(*value),
+            operand => Err(format!("expected value, got {operand:?}")),
+        })
+        .collect()
+}
+
+fn parse_instruction<'a>(
+    mnemonic: &str,
+    operands: &[Operand<'a>],
+) -> Result<Statement<'a>, String> {
+    use Operand::*;
+    use Register::Accumulator as A;
+
+    let instruction = |instruction: Instruction| {
+        Ok(Statement::Instruction {
+            instruction,
+            immediate: None,
+        })
+    };
+    let with_immediate = |instruction: Instruction, value: self::Value<'a>| {
+        Ok(Statement::Instruction {
+            instruction,
+            immediate: Some(value),
+        })
+    };
+
+    match (mnemonic, operands) {
+        ("nop", []) => instruction(InstructionNop.into()),
+
+        ("ld", [Reg(A), Reg(reg)]) => instruction(InstructionLoad::a_reg(*reg).into()),
+        ("ld", [Reg(A), Value(value)]) => with_immediate(InstructionLoad::a_byte.into(), *value),
+        ("ld", [AtReg(A), Reg(reg)]) => instruction(InstructionLoad::atA_reg(*reg).into()),
+        ("ld", [Reg(reg), AtReg(A)]) => instruction(InstructionLoad::reg_atA(*reg).into()),
+        ("ld", [Reg(reg), Reg(A)]) => instruction(InstructionLoad::reg_a(*reg).into()),
+
+        ("add", [Reg(A), Reg(reg)]) => instruction(InstructionAdd::a_reg(*reg).into()),
+        ("add", [Reg(A), AtReg(reg)]) => instruction(InstructionAdd::a_atReg(*reg).into()),
+        ("sub", [Reg(A), Reg(reg)]) => instruction(InstructionSub::a_reg(*reg).into()),
+        ("sub", [Reg(A), AtReg(reg)]) => instruction(InstructionSub::a_atReg(*reg).into()),
+        ("and", [Reg(A), Reg(reg)]) => instruction(InstructionAnd::a_reg(*reg).into()),
+        ("and", [Reg(A), AtReg(reg)]) => instruction(InstructionAnd::a_atReg(*reg).into()),
+        ("or", [Reg(A), Reg(reg)]) => instruction(InstructionOr::a_reg(*reg).into()),
+        ("or", [Reg(A), AtReg(reg)]) => instruction(InstructionOr::a_atReg(*reg).into()),
+        ("xor", [Reg(A), Reg(reg)]) => instruction(InstructionXor::a_reg(*reg).into()),
+        ("xor", [Reg(A), AtReg(reg)]) => instruction(InstructionXor::a_atReg(*reg).into()),
+
+        ("not", [Reg(reg)]) => instruction(InstructionNot::reg(*reg).into()),
+        ("not", [AtReg(reg)]) => instruction(InstructionNot::atReg(*reg).into()),
+        ("inc", [Reg(reg)]) => instruction(InstructionInc::reg(*reg).into()),
+        ("inc", [AtReg(reg)]) => instruction(InstructionInc::atReg(*reg).into()),
+        ("dec", [Reg(reg)]) => instruction(InstructionDec::reg(*reg).into()),
+        ("dec", [AtReg(reg)]) => instruction(InstructionDec::atReg(*reg).into()),
+        ("shl", [Reg(reg)]) => instruction(InstructionLeftShift::reg(*reg).into()),
+        ("shl", [AtReg(reg)]) => instruction(InstructionLeftShift::atReg(*reg).into()),
+        ("shr", [Reg(reg)]) => instruction(InstructionRightShift::reg(*reg).into()),
+        ("shr", [AtReg(reg)]) => instruction(InstructionRightShift::atReg(*reg).into()),
+
+        ("jmp", [Reg(reg)]) => instruction(InstructionJump::reg(*reg).into()),
+        ("jmp", [AtReg(reg)]) => instruction(InstructionJump::atReg(*reg).into()),
+        ("jmp", [Value(value)]) => {
+            with_immediate(InstructionJump::byte { if_z: false }.into(), *value)
+        }
+        ("jz", [Reg(reg)]) => instruction(InstructionJump::ifZ_reg(*reg).into()),
+        ("jz", [AtReg(reg)]) => instruction(InstructionJump::ifZ_atReg(*reg).into()),
+        ("jz", [Value(value)]) => {
+            with_immediate(InstructionJump::byte { if_z: true }.into(), *value)
+        }
+
+        ("push", [Reg(reg)]) => instruction(InstructionPush::reg(*reg).into()),
+        ("push", [AtReg(reg)]) => instruction(InstructionPush::atReg(*reg).into()),
+        ("pop", [Reg(reg)]) => instruction(InstructionPop::reg(*reg).into()),
+        ("pop", [AtReg(reg)]) => instruction(InstructionPop::atReg(*reg).into()),
+
+        ("call", [Reg(reg)]) => instruction(InstructionCall::reg(*reg).into()),
+        ("call", [Value(value)]) => {
+            with_immediate(InstructionCall::byte { if_z: false }.into(), *value)
+        }
+        ("callz", [Reg(reg)]) => instruction(InstructionCall::ifZ_reg(*reg).into()),
+        ("callz", [Value(value)]) => {
+            with_immediate(InstructionCall::byte { if_z: true }.into(), *value)
+        }
+        ("ret", []) => instruction(InstructionRet { if_z: false }.into()),
+        ("retz", []) => instruction(InstructionRet { if_z: true }.into()),
+
+        ("cmp", [Reg(A), Reg(reg)]) => instruction(InstructionCompare::a_reg(*reg).into()),
+        ("cmp", [Reg(A), Value(value)]) => {
+            with_immediate(InstructionCompare::a_byte.into(), *value)
+        }
+        ("cmp", [AtReg(A), Value(value)]) => {
+            with_immediate(InstructionCompare::atA_byte.into(), *value)
+        }
+
+        ("rep", []) => instruction(InstructionReplicate.into()),
+        ("prot", [AtReg(A)]) => instruction(InstructionProtect.into()),
+        ("unprot", [AtReg(A)]) => instruction(InstructionUnprotect.into()),
+        ("rnd", [Reg(reg)]) => instruction(InstructionRandom::reg(*reg).into()),
+
+        ("sense", [Value(self::Value::Label(sensor))]) => {
+            let sensor = match *sensor {
+                "dir" => InstructionSense::direction,
+                "x" => InstructionSense::x,
+                "y" => InstructionSense::y,
+                "tick" => InstructionSense::tick,
+                "role" => InstructionSense::role,
+                sensor => return Err(format!("unknown sensor `{sensor}`")),
+            };
+            instruction(sensor.into())
+        }
+
+        (mnemonic, operands) => Err(format!(
+            "unknown instruction `{mnemonic}` with operands {operands:?}"
+        )),
+    }
+}
+
+fn parse_operand(operand: &str) -> Result<Operand<'_>, String> {
+    if let Some(inner) = operand.strip_prefix('[').and_then(|o| o.strip_suffix(']')) {
+        return parse_register(inner.trim())
+            .map(Operand::AtReg)
+            .ok_or_else(|| format!("expected register in `{operand}`"));
+    }
+
+    if let Some(register) = parse_register(operand) {
+        return Ok(Operand::Reg(register));
+    }
+
+    parse_value(operand).map(Operand::Value)
+}
+
+fn parse_register(name: &str) -> Option<Register> {
+    name.to_ascii_lowercase().parse().ok()
+}
+
+fn parse_value(value: &str) -> Result<Value<'_>, String> {
+    let number = if let Some(hex) = value.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(binary) = value.strip_prefix("0b") {
+        u64::from_str_radix(&binary.replace('_', ""), 2)
+    } else if value.starts_with(|c: char| c.is_ascii_digit()) {
+        value.parse()
+    } else if is_identifier(value) {
+        return Ok(Value::Label(value));
+    } else {
+        return Err(format!("invalid value `{value}`"));
+    };
+
+    number
+        .map(Value::Number)
+        .map_err(|err| format!("invalid number `{value}`: {err}"))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[test]
+fn test_assemble_disassemble_roundtrip() {
+    let config = VmConfig {
+        opcode_revision: OpcodeRevision::V3,
+        ..Default::default()
+    };
+
+    for opcode in 0..=u8::MAX {
+        let memory = [opcode, 0x2a];
+        let instruction = DisassembledInstruction::decode(&memory, 0, &config);
+
+        let bytes = assemble(&instruction.to_string(), &config).unwrap();
+        assert_eq!(bytes, memory[..instruction.len], "{instruction}");
+    }
+}
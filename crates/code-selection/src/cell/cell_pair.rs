@@ -1,135 +1,282 @@
 use crate::*;
+use ::rand::{rngs::SmallRng, SeedableRng};
 
 pub struct CellPair<'a> {
     pub main: &'a mut CellState,
     pub neighbor: &'a mut CellState,
     pub cycles_to_run: usize,
+    pub config: VmConfig,
+    pub context: PairContext,
+    /// Deterministic source of randomness for the instructions.
+    pub rng: SmallRng,
+    pub stats: TickStats,
+    /// Memory writes of the current step, recorded only by [`CellPair::step`].
+    memory_writes: Option<Vec<MemoryWrite>>,
+    /// Stack writes of the current step, recorded only by [`CellPair::step`].
+    stack_writes: Option<Vec<StackWrite>>,
+    /// Neighbor memory indices written during the turn, recorded only if enabled by
+    /// [`CellPair::with_neighbor_write_log`].
+    neighbor_write_log: Option<Vec<usize>>,
+    /// Provenance of the value moved by the current instruction, written along with it.
+    /// Values written without it originate from the main cell.
+    carried: Option<Provenance>,
 }
 
 impl<'a> CellPair<'a> {
-    pub fn new(main: &'a mut CellState, neighbor: &'a mut CellState) -> Self {
+    pub fn new(main: &'a mut CellState, neighbor: &'a mut CellState, config: VmConfig) -> Self {
         Self {
             main,
             neighbor,
             cycles_to_run: 37,
+            config,
+            context: PairContext {
+                neighbor_index: 1,
+                ..Default::default()
+            },
+            rng: SmallRng::seed_from_u64(0),
+            stats: TickStats::default(),
+            memory_writes: None,
+            stack_writes: None,
+            neighbor_write_log: None,
+            carried: None,
         }
     }
 
-    /// Handle single instruction execution.
+    pub fn with_context(mut self, context: PairContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Record which neighbor bytes the pair writes, see [`CellPair::take_neighbor_write_log`].
+    pub fn with_neighbor_write_log(mut self) -> Self {
+        self.neighbor_write_log = Some(Vec::new());
+        self
+    }
+
+    /// Neighbor memory indices written since the log was enabled, in order of the writes.
+    pub fn take_neighbor_write_log(&mut self) -> Vec<usize> {
+        self.neighbor_write_log.take().unwrap_or_default()
+    }
+
+    /// Execute instructions until the pair runs out of cycles.
     #[inline(always)]
     pub fn tick(&mut self) {
         loop {
             let instruction = self.read_instruction();
+            self.stats.executed[instruction.variant_index()] += 1;
+            self.carried = None;
             instruction.process(self);
 
-            if self.cycles_to_run == 0 {
+            if !self.spend_cycle() {
                 break;
             }
-
-            self.cycles_to_run -= 1;
         }
     }
 
-    pub fn read_instruction(&mut self) -> Instruction {
-        let opcode = self.advance_pc();
-
-        #[allow(clippy::unusual_byte_groupings)]
-        match opcode {
-            0b0000_0000 => InstructionNop.into(),
+    /// Execute single instruction and record what it changed.
+    pub fn step(&mut self) -> StepRecord {
+        let pc = self.get_reg_pc();
+        let registers = self.main.registers;
+        let opcode = self.get_memory(pc);
+
+        self.memory_writes = Some(Vec::new());
+        self.stack_writes = Some(Vec::new());
+        let instruction = self.read_instruction();
+        self.stats.executed[instruction.variant_index()] += 1;
+        self.carried = None;
+        instruction.process(self);
+        let memory_writes = self.memory_writes.take().unwrap_or_default();
+        let stack_writes = self.stack_writes.take().unwrap_or_default();
+
+        let register_changes = registers
+            .iter()
+            .zip(self.main.registers.iter())
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .map(|(index, (&old, &new))| RegisterChange {
+                register: Register::from(index as u8),
+                old,
+                new,
+            })
+            .collect();
+
+        StepRecord {
+            pc,
+            opcode,
+            instruction,
+            register_changes,
+            memory_writes,
+            stack_writes,
+            is_turn_over: !self.spend_cycle(),
+        }
+    }
 
-            // skip acc register (0x00 is nop) because `ld a a` does not make sense
-            0b00000_001..=0b00000_111 => InstructionLoad::a_reg(opcode.into()).into(),
-            0b00001_000..=0b00001_111 => InstructionLoad::atA_reg(opcode.into()).into(),
-            0b00010_000..=0b00010_111 => InstructionLoad::reg_atA(opcode.into()).into(),
-            0b00011_000 => InstructionLoad::a_byte.into(),
-            // same as for InstructionLoad::a_reg (0b00000_001..=0b00000_111)
-            0b00011_001..=0b00011_111 => InstructionLoad::reg_a(opcode.into()).into(),
+    /// Take a cycle from the budget. Returns false if there were no cycles left.
+    #[inline(always)]
+    fn spend_cycle(&mut self) -> bool {
+        if self.cycles_to_run == 0 {
+            return false;
+        }
 
-            0b00100_000..=0b00100_111 => InstructionAdd::a_reg(opcode.into()).into(),
-            0b00101_000..=0b00101_111 => InstructionAdd::a_atReg(opcode.into()).into(),
+        self.cycles_to_run -= 1;
+        true
+    }
 
-            0b00110_000..=0b00110_111 => InstructionSub::a_reg(opcode.into()).into(),
-            0b00111_000..=0b00111_111 => InstructionSub::a_atReg(opcode.into()).into(),
+    pub fn read_instruction(&mut self) -> Instruction {
+        let opcode = self.advance_pc();
+        Instruction::decode(opcode, self.config.opcode_revision)
+    }
 
-            0b01000_000..=0b01000_111 => InstructionAnd::a_reg(opcode.into()).into(),
-            0b01001_000..=0b01001_111 => InstructionAnd::a_atReg(opcode.into()).into(),
+    /// Map the address to the cell it belongs to and the index inside of the cell memory.
+    #[inline(always)]
+    fn resolve_address(&self, address: Word) -> (&CellState, usize) {
+        let address = address as usize % self.config.address_space();
 
-            0b01010_000..=0b01010_111 => InstructionOr::a_reg(opcode.into()).into(),
-            0b01011_000..=0b01011_111 => InstructionOr::a_atReg(opcode.into()).into(),
+        if address < self.config.memory_size {
+            (&*self.main, address)
+        } else {
+            (&*self.neighbor, address - self.config.memory_size)
+        }
+    }
 
-            0b01100_000..=0b01100_111 => InstructionXor::a_reg(opcode.into()).into(),
-            0b01101_000..=0b01101_111 => InstructionXor::a_atReg(opcode.into()).into(),
+    #[inline(always)]
+    fn resolve_address_mut(&mut self, address: Word) -> (&mut CellState, usize) {
+        let address = address as usize % self.config.address_space();
 
-            0b01110_000..=0b01110_111 => InstructionNot::reg(opcode.into()).into(),
-            0b01111_000..=0b01111_111 => InstructionNot::atReg(opcode.into()).into(),
+        if address < self.config.memory_size {
+            (&mut *self.main, address)
+        } else {
+            (&mut *self.neighbor, address - self.config.memory_size)
+        }
+    }
 
-            0b10000_000..=0b10000_111 => InstructionJump::reg(opcode.into()).into(),
-            0b10001_000..=0b10001_111 => InstructionJump::atReg(opcode.into()).into(),
-            0b10010_000..=0b10010_111 => InstructionJump::ifZ_reg(opcode.into()).into(),
-            0b10011_000..=0b10011_111 => InstructionJump::ifZ_atReg(opcode.into()).into(),
+    /// Check if the address belongs to the main cell.
+    #[inline(always)]
+    pub fn is_main_address(&self, address: Word) -> bool {
+        (address as usize % self.config.address_space()) < self.config.memory_size
+    }
 
-            0b10100_000..=0b10100_111 => InstructionPush::reg(opcode.into()).into(),
-            0b10101_000..=0b10101_111 => InstructionPush::atReg(opcode.into()).into(),
+    /// Get the value of the memory cell at the given address.
+    #[inline(always)]
+    pub fn get_memory(&self, address: Word) -> u8 {
+        let (cell, index) = self.resolve_address(address);
+        cell.memory[index]
+    }
 
-            0b10110_000..=0b10110_111 => InstructionPop::reg(opcode.into()).into(),
-            0b10111_000..=0b10111_111 => InstructionPop::atReg(opcode.into()).into(),
+    #[inline(always)]
+    pub fn get_memory_at_acc(&self) -> u8 {
+        self.get_memory(self.get_reg_acc())
+    }
 
-            0b11000_000..=0b11000_111 => InstructionCall::reg(opcode.into()).into(),
-            0b11001_000..=0b11001_111 => InstructionCall::ifZ_reg(opcode.into()).into(),
+    #[inline(always)]
+    pub fn get_memory_at_reg(&self, register: Register) -> u8 {
+        self.get_memory(self.get_reg(register))
+    }
 
-            0b11010_000..=0b11010_111 => InstructionLeftShift::reg(opcode.into()).into(),
-            0b11011_000..=0b11011_111 => InstructionLeftShift::atReg(opcode.into()).into(),
+    /// Read a register sized little-endian value starting at the given address.
+    #[inline(always)]
+    pub fn get_memory_word(&self, address: Word) -> Word {
+        match self.config.address_mode {
+            AddressMode::Bits8 => self.get_memory(address) as Word,
+            AddressMode::Bits16 => {
+                let low = self.get_memory(address) as Word;
+                let high = self.get_memory(address.wrapping_add(1)) as Word;
+                low | (high << 8)
+            }
+        }
+    }
 
-            0b11100_000..=0b11100_111 => InstructionRightShift::reg(opcode.into()).into(),
-            0b11101_000..=0b11101_111 => InstructionRightShift::atReg(opcode.into()).into(),
+    #[inline(always)]
+    pub fn get_memory_word_at_reg(&self, register: Register) -> Word {
+        self.get_memory_word(self.get_reg(register))
+    }
 
-            0b11110_000 => InstructionCompare::a_byte.into(),
-            0b11110_001..=0b11110_111 => InstructionCompare::a_reg(opcode.into()).into(),
-            0b11111_000 => InstructionCompare::atA_byte.into(),
+    /// Set the value of the memory cell at the given address.
+    /// Writes into bytes protected by another cell are dropped and penalized.
+    #[inline(always)]
+    pub fn set_memory(&mut self, address: Word, value: u8) {
+        if let Some(penalty) = self.config.write_protection {
+            if !self.is_writable(address) {
+                self.apply_protection_penalty(penalty);
+                return;
+            }
+        }
 
-            0b11111_001 => InstructionReplicate.into(),
+        if self.memory_writes.is_some() {
+            let (cell, index) = self.resolve_address(address);
+            let write = MemoryWrite {
+                address: address as usize % self.config.address_space(),
+                old: cell.memory[index],
+                new: value,
+            };
 
-            0b11111_010 => InstructionJump::byte { if_z: false }.into(),
-            0b11111_011 => InstructionJump::byte { if_z: true }.into(),
+            if let Some(memory_writes) = &mut self.memory_writes {
+                memory_writes.push(write);
+            }
+        }
 
-            0b11111_100 => InstructionCall::byte { if_z: true }.into(),
-            0b11111_101 => InstructionCall::byte { if_z: false }.into(),
+        let provenance = self.carried.unwrap_or_else(|| self.origin());
+        let address = address as usize % self.config.address_space();
+        let (cell, index) = if address < self.config.memory_size {
+            (&mut *self.main, address)
+        } else {
+            self.stats.neighbor_writes += 1;
+            let index = address - self.config.memory_size;
+            if let Some(log) = &mut self.neighbor_write_log {
+                log.push(index);
+            }
+            (&mut *self.neighbor, index)
+        };
 
-            0b111_11_110 => InstructionRet { if_z: false }.into(),
-            0b111_11_111 => InstructionRet { if_z: true }.into(),
+        cell.memory[index] = value;
+        if let Some(shadow) = &mut cell.provenance {
+            shadow.memory[index] = provenance;
         }
     }
 
-    /// Get the value of the memory cell at the given address.
+    /// Write a register sized little-endian value starting at the given address.
     #[inline(always)]
-    pub fn get_memory(&self, address: u8) -> u8 {
-        if address < CellState::MEMORY_SIZE as u8 {
-            self.main.memory[address as usize]
-        } else {
-            self.neighbor.memory[address as usize - CellState::MEMORY_SIZE]
+    pub fn set_memory_word(&mut self, address: Word, value: Word) {
+        match self.config.address_mode {
+            AddressMode::Bits8 => self.set_memory(address, value as u8),
+            AddressMode::Bits16 => {
+                self.set_memory(address, value as u8);
+                self.set_memory(address.wrapping_add(1), (value >> 8) as u8);
+            }
         }
     }
 
+    /// Check if the main cell is allowed to write at the given address.
     #[inline(always)]
-    pub fn get_memory_at_acc(&self) -> u8 {
-        self.get_memory(self.get_reg_acc())
-    }
+    pub fn is_writable(&self, address: Word) -> bool {
+        let (cell, index) = self.resolve_address(address);
 
-    #[inline(always)]
-    pub fn get_memory_at_reg(&self, register: Register) -> u8 {
-        self.get_memory(self.get_reg(register))
+        cell.tags
+            .get(index)
+            .is_none_or(|tag| tag.is_writable_by(self.context.main_index))
     }
 
-    /// Set the value of the memory cell at the given address.
-    #[inline(always)]
-    pub fn set_memory(&mut self, address: u8, value: u8) {
-        if address < CellState::MEMORY_SIZE as u8 {
-            self.main.memory[address as usize] = value;
-        } else {
-            self.neighbor.memory[address as usize - CellState::MEMORY_SIZE] = value;
+    fn apply_protection_penalty(&mut self, penalty: ProtectionPenalty) {
+        match penalty {
+            ProtectionPenalty::DropWrite => {}
+            ProtectionPenalty::Cycles(cycles) => {
+                self.cycles_to_run = self.cycles_to_run.saturating_sub(cycles);
+            }
+            ProtectionPenalty::EndTurn => self.cycles_to_run = 0,
         }
     }
 
+    /// Get the write protection tag of the memory cell at the given address.
+    pub fn get_tag_mut(&mut self, address: Word) -> &mut MemoryTag {
+        let (cell, index) = self.resolve_address_mut(address);
+        cell.get_tag_mut(index)
+    }
+
     #[inline(always)]
     pub fn set_memory_at_acc(&mut self, value: u8) {
         self.set_memory(self.get_reg_acc(), value);
@@ -140,81 +287,185 @@ impl<'a> CellPair<'a> {
         self.set_memory(self.get_reg(register), value);
     }
 
+    /// Mask of the meaningful register bits in the current address mode.
+    #[inline(always)]
+    pub fn word_mask(&self) -> Word {
+        self.config.address_mode.word_mask()
+    }
+
     /// Return the value at the current program counter and advance it.
     #[inline(always)]
     pub fn advance_pc(&mut self) -> u8 {
-        let result = self.get_memory(self.main.registers[CellState::REGISTER_PROGRAM_COUNTER]);
+        let pc = self.get_reg_pc();
+        let result = self.get_memory(pc);
+        self.set_reg_pc(pc.wrapping_add(1));
 
-        self.main.registers[CellState::REGISTER_PROGRAM_COUNTER] =
-            self.main.registers[CellState::REGISTER_PROGRAM_COUNTER].wrapping_add(1);
+        result
+    }
+
+    /// Return the register sized immediate value at the current program counter and advance it.
+    #[inline(always)]
+    pub fn advance_pc_word(&mut self) -> Word {
+        let pc = self.get_reg_pc();
+        let result = self.get_memory_word(pc);
+        self.set_reg_pc(pc.wrapping_add(self.config.address_mode.word_bytes()));
 
         result
     }
 
     /// Decrease the stack pointer and write the value at the new address.
+    /// Returns false if the push trapped, the instruction must stop then.
     #[inline(always)]
-    pub fn push_to_stack(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_STACK_POINTER] =
-            self.main.registers[CellState::REGISTER_STACK_POINTER].wrapping_sub(1);
-        self.set_memory(
-            self.main.registers[CellState::REGISTER_STACK_POINTER],
-            value,
-        );
+    pub fn push_to_stack(&mut self, value: Word) -> bool {
+        let sp = self.get_reg_sp();
+
+        match self.config.stack_mode {
+            StackMode::Wrap => {
+                let sp = sp.wrapping_sub(self.config.address_mode.word_bytes());
+                self.set_reg_sp(sp);
+                self.set_memory_word(self.get_reg_sp(), value);
+            }
+            StackMode::Separate { size } => {
+                let sp = (sp as usize % size + size - 1) % size;
+                self.set_reg_sp(sp as Word);
+                self.set_stack(size, sp, value);
+            }
+            StackMode::Trap { size } => {
+                let sp = sp as usize;
+                if sp == 0 || sp > size {
+                    self.trap();
+                    return false;
+                }
+
+                self.set_reg_sp(sp as Word - 1);
+                self.set_stack(size, sp - 1, value);
+            }
+        }
+
+        true
     }
 
-    /// Increase the stack pointer and return the value at the new address.
+    /// Increase the stack pointer and return the value at the old address.
+    /// Returns `None` if the pop trapped, the instruction must stop then.
     #[inline(always)]
-    pub fn pop_from_stack(&mut self) -> u8 {
-        let result = self.get_memory(self.main.registers[CellState::REGISTER_STACK_POINTER]);
+    pub fn pop_from_stack(&mut self) -> Option<Word> {
+        let sp = self.get_reg_sp();
 
-        self.main.registers[CellState::REGISTER_STACK_POINTER] =
-            self.main.registers[CellState::REGISTER_STACK_POINTER].wrapping_add(1);
+        match self.config.stack_mode {
+            StackMode::Wrap => {
+                self.carry_memory(sp);
+                let result = self.get_memory_word(sp);
+                self.set_reg_sp(sp.wrapping_add(self.config.address_mode.word_bytes()));
 
-        result
+                Some(result)
+            }
+            StackMode::Separate { size } => {
+                let sp = sp as usize % size;
+                self.carry_stack(sp);
+                self.set_reg_sp(((sp + 1) % size) as Word);
+
+                Some(self.main.stack.get(sp).copied().unwrap_or_default())
+            }
+            StackMode::Trap { size } => {
+                let sp = sp as usize;
+                if sp >= size {
+                    self.trap();
+                    return None;
+                }
+
+                self.carry_stack(sp);
+                self.set_reg_sp(sp as Word + 1);
+
+                Some(self.main.stack.get(sp).copied().unwrap_or_default())
+            }
+        }
+    }
+
+    /// Write the slot of the stack outside of the pair memory, the stack is allocated on
+    /// the first write.
+    fn set_stack(&mut self, size: usize, index: usize, value: Word) {
+        if self.main.stack.len() != size {
+            self.main.stack.resize(size, 0);
+        }
+
+        if let Some(stack_writes) = &mut self.stack_writes {
+            stack_writes.push(StackWrite {
+                index,
+                old: self.main.stack[index],
+                new: value,
+            });
+        }
+
+        self.main.stack[index] = value;
+
+        let origin = self.origin();
+        let provenance = self.carried.unwrap_or(origin);
+        if let Some(shadow) = &mut self.main.provenance {
+            if shadow.stack.len() != size {
+                shadow.stack.resize(size, origin);
+            }
+            shadow.stack[index] = provenance;
+        }
+    }
+
+    /// Make the following writes of the instruction carry the provenance of the stack slot
+    /// outside of the pair memory.
+    fn carry_stack(&mut self, index: usize) {
+        self.carried = self
+            .main
+            .provenance
+            .as_ref()
+            .and_then(|shadow| shadow.stack.get(index).copied());
+    }
+
+    /// End the pair's turn because of the invalid stack operation.
+    fn trap(&mut self) {
+        self.cycles_to_run = 0;
+        self.stats.stack_traps += 1;
     }
 
     #[inline(always)]
-    pub fn get_reg_acc(&self) -> u8 {
+    pub fn get_reg_acc(&self) -> Word {
         self.main.registers[CellState::REGISTER_ACCUMULATOR]
     }
 
     #[inline(always)]
-    pub fn get_reg_flags(&self) -> u8 {
+    pub fn get_reg_flags(&self) -> Word {
         self.main.registers[CellState::REGISTER_FLAGS]
     }
 
     #[inline(always)]
-    pub fn get_reg_pc(&self) -> u8 {
+    pub fn get_reg_pc(&self) -> Word {
         self.main.registers[CellState::REGISTER_PROGRAM_COUNTER]
     }
 
     #[inline(always)]
-    pub fn get_reg_sp(&self) -> u8 {
+    pub fn get_reg_sp(&self) -> Word {
         self.main.registers[CellState::REGISTER_STACK_POINTER]
     }
 
     #[inline(always)]
-    pub fn get_reg_b(&self) -> u8 {
+    pub fn get_reg_b(&self) -> Word {
         self.main.registers[CellState::REGISTER_B]
     }
 
     #[inline(always)]
-    pub fn get_reg_c(&self) -> u8 {
+    pub fn get_reg_c(&self) -> Word {
         self.main.registers[CellState::REGISTER_C]
     }
 
     #[inline(always)]
-    pub fn get_reg_d(&self) -> u8 {
+    pub fn get_reg_d(&self) -> Word {
         self.main.registers[CellState::REGISTER_D]
     }
 
     #[inline(always)]
-    pub fn get_reg_e(&self) -> u8 {
+    pub fn get_reg_e(&self) -> Word {
         self.main.registers[CellState::REGISTER_E]
     }
 
     #[inline(always)]
-    pub fn get_reg(&self, register: Register) -> u8 {
+    pub fn get_reg(&self, register: Register) -> Word {
         match register {
             Register::Accumulator => self.main.registers[CellState::REGISTER_ACCUMULATOR],
             Register::Flags => self.main.registers[CellState::REGISTER_FLAGS],
@@ -228,7 +479,7 @@ impl<'a> CellPair<'a> {
     }
 
     #[inline(always)]
-    pub fn get_reg_mut(&mut self, register: Register) -> &mut u8 {
+    pub fn get_reg_mut(&mut self, register: Register) -> &mut Word {
         match register {
             Register::Accumulator => &mut self.main.registers[CellState::REGISTER_ACCUMULATOR],
             Register::Flags => &mut self.main.registers[CellState::REGISTER_FLAGS],
@@ -244,47 +495,92 @@ impl<'a> CellPair<'a> {
     }
 
     #[inline(always)]
-    pub fn set_reg_acc(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_ACCUMULATOR] = value;
+    pub fn set_reg_acc(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_ACCUMULATOR, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_flags(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_FLAGS] = value;
+    pub fn set_reg_flags(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_FLAGS, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_pc(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_PROGRAM_COUNTER] = value;
+    pub fn set_reg_pc(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_PROGRAM_COUNTER, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_sp(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_STACK_POINTER] = value;
+    pub fn set_reg_sp(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_STACK_POINTER, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_b(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_B] = value;
+    pub fn set_reg_b(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_B, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_c(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_C] = value;
+    pub fn set_reg_c(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_C, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_d(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_D] = value;
+    pub fn set_reg_d(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_D, value);
     }
 
     #[inline(always)]
-    pub fn set_reg_e(&mut self, value: u8) {
-        self.main.registers[CellState::REGISTER_E] = value;
+    pub fn set_reg_e(&mut self, value: Word) {
+        self.set_register(CellState::REGISTER_E, value);
+    }
+
+    /// Set the register by its index along with the provenance of the value.
+    #[inline(always)]
+    fn set_register(&mut self, index: usize, value: Word) {
+        self.main.registers[index] = value & self.word_mask();
+
+        if self.main.provenance.is_some() {
+            let provenance = self.carried.unwrap_or_else(|| self.origin());
+            if let Some(shadow) = &mut self.main.provenance {
+                shadow.registers[index] = provenance;
+            }
+        }
     }
 
+    /// Provenance of values computed by the current instruction.
     #[inline(always)]
-    pub fn set_reg(&mut self, register: Register, value: u8) {
+    pub fn origin(&self) -> Provenance {
+        Provenance::computed(self.context.main_index, self.context.tick)
+    }
+
+    /// Provenance of the memory byte, `None` if the cell does not track it.
+    pub fn get_memory_provenance(&self, address: Word) -> Option<Provenance> {
+        let (cell, index) = self.resolve_address(address);
+        cell.provenance.as_ref().map(|shadow| shadow.memory[index])
+    }
+
+    /// Provenance of the register of the main cell, `None` if the cell does not track it.
+    pub fn get_register_provenance(&self, register: Register) -> Option<Provenance> {
+        self.main
+            .provenance
+            .as_ref()
+            .map(|shadow| shadow.registers[register as usize])
+    }
+
+    /// Make the following writes of the instruction carry the provenance of the memory byte.
+    #[inline(always)]
+    pub fn carry_memory(&mut self, address: Word) {
+        self.carried = self.get_memory_provenance(address);
+    }
+
+    /// Make the following writes of the instruction carry the provenance of the register.
+    #[inline(always)]
+    pub fn carry_register(&mut self, register: Register) {
+        self.carried = self.get_register_provenance(register);
+    }
+
+    #[inline(always)]
+    pub fn set_reg(&mut self, register: Register, value: Word) {
         match register {
             Register::Accumulator => self.set_reg_acc(value),
             Register::Flags => self.set_reg_flags(value),
@@ -298,7 +594,7 @@ impl<'a> CellPair<'a> {
     }
 
     #[inline(always)]
-    pub fn get_flag(&self, mask: u8) -> bool {
+    pub fn get_flag(&self, mask: Word) -> bool {
         self.get_reg_flags() & mask != 0
     }
 
@@ -318,7 +614,7 @@ impl<'a> CellPair<'a> {
     }
 
     #[inline(always)]
-    pub fn set_flag(&mut self, mask: u8, value: bool) {
+    pub fn set_flag(&mut self, mask: Word, value: bool) {
         if value {
             self.set_reg_flags(self.get_reg_flags() | mask);
         } else {
@@ -341,3 +637,115 @@ impl<'a> CellPair<'a> {
         self.set_flag(CellState::FLAG_C_MASK, value);
     }
 }
+
+#[test]
+fn test_step_matches_tick() {
+    use ::rand::{rngs::SmallRng, SeedableRng};
+
+    let config = VmConfig {
+        opcode_revision: OpcodeRevision::V3,
+        ..Default::default()
+    };
+    let mut rng = SmallRng::seed_from_u64(7);
+
+    for _ in 0..100 {
+        let (mut main0, mut neighbor0) = (
+            CellState::random(&config, &mut rng),
+            CellState::random(&config, &mut rng),
+        );
+        let (mut main1, mut neighbor1) = (main0.clone(), neighbor0.clone());
+
+        CellPair::new(&mut main0, &mut neighbor0, config).tick();
+
+        let mut pair = CellPair::new(&mut main1, &mut neighbor1, config);
+        while !pair.step().is_turn_over {}
+
+        assert_eq!(main0.memory, main1.memory);
+        assert_eq!(main0.registers, main1.registers);
+        assert_eq!(neighbor0.memory, neighbor1.memory);
+    }
+}
+
+#[test]
+fn test_step_records() {
+    let config = VmConfig {
+        stack_mode: StackMode::Separate { size: 4 },
+        ..Default::default()
+    };
+    let source = "
+        ld a, 0x90
+        ld c, a
+        ld [a], c       ; the neighbor byte at 0x10
+        push c
+    ";
+    let program = assemble(source, &config).unwrap();
+    let mut main = Stamp::program_cell(program.clone(), &config).unwrap();
+    let mut neighbor = Stamp::program_cell(vec![0x55; 0x20], &config).unwrap();
+
+    let mut pair = CellPair::new(&mut main, &mut neighbor, config);
+    pair.cycles_to_run = 3;
+    let steps = (0..4).map(|_| pair.step()).collect::<Vec<_>>();
+
+    let change = |register, old, new| RegisterChange { register, old, new };
+    let record =
+        |pc: usize, register_changes, memory_writes, stack_writes, is_turn_over| StepRecord {
+            pc: pc as Word,
+            opcode: program[pc],
+            instruction: Instruction::decode(program[pc], config.opcode_revision),
+            register_changes,
+            memory_writes,
+            stack_writes,
+            is_turn_over,
+        };
+
+    assert_eq!(
+        steps,
+        [
+            record(
+                0,
+                vec![
+                    change(Register::Accumulator, 0, 0x90),
+                    change(Register::ProgramCounter, 0, 2),
+                ],
+                vec![],
+                vec![],
+                false,
+            ),
+            record(
+                2,
+                vec![
+                    change(Register::ProgramCounter, 2, 3),
+                    change(Register::C, 0, 0x90),
+                ],
+                vec![],
+                vec![],
+                false,
+            ),
+            record(
+                3,
+                vec![change(Register::ProgramCounter, 3, 4)],
+                vec![MemoryWrite {
+                    address: 0x90,
+                    old: 0x55,
+                    new: 0x90,
+                }],
+                vec![],
+                false,
+            ),
+            record(
+                4,
+                vec![
+                    change(Register::ProgramCounter, 4, 5),
+                    change(Register::StackPointer, 0, 3),
+                ],
+                vec![],
+                vec![StackWrite {
+                    index: 3,
+                    old: 0,
+                    new: 0x90,
+                }],
+                true,
+            ),
+        ]
+    );
+}
@@ -1,20 +1,32 @@
-use crate::{AreaSize, RelativePosition};
+use crate::{
+    fnv1a_hash, AddressMode, CellProvenance, Lineage, MemoryTag, RelativePosition, VmConfig,
+};
+use ::rand::Rng;
 use macroquad::{color::Color, texture::Image};
 
-#[derive(Debug, Clone, Copy)]
+/// Register value. Only the lower byte is used in [`AddressMode::Bits8`].
+pub type Word = u16;
+
+#[derive(Debug, Clone)]
 pub struct CellState {
     /// Memory of the cell. At each simulation tick a random pair of adjacent cells is selected.
-    pub memory: [u8; Self::MEMORY_SIZE],
+    pub memory: Vec<u8>,
     /// Accumulator, flags, program counter, stack pointer and 4 general purpose registers.
-    pub registers: [u8; 8],
+    pub registers: [Word; Self::REGISTERS_COUNT],
+    /// Write protection metadata of each memory byte. Empty until any byte gets protected.
+    pub tags: Vec<MemoryTag>,
+    /// Stack of [`crate::StackMode::Separate`] and [`crate::StackMode::Trap`] modes.
+    /// Empty until anything is pushed.
+    pub stack: Vec<Word>,
+    /// Genome identity, `None` unless the world tracks lineages.
+    pub lineage: Option<Lineage>,
+    /// Origins of the memory bytes and registers, `None` unless the world tracks provenance.
+    pub provenance: Option<Box<CellProvenance>>,
 }
 
 impl CellState {
-    pub const CANVAS_SIZE: AreaSize = AreaSize {
-        width: 7,
-        height: 7,
-    };
-    pub const MEMORY_SIZE: usize = (u8::MAX as usize + 1) / 2;
+    pub const DEFAULT_MEMORY_SIZE: usize = (u8::MAX as usize).div_ceil(2);
+    pub const REGISTERS_COUNT: usize = 8;
     pub const REGISTER_ACCUMULATOR: usize = 0;
     pub const REGISTER_FLAGS: usize = 1;
     pub const REGISTER_PROGRAM_COUNTER: usize = 2;
@@ -24,55 +36,148 @@ impl CellState {
     pub const REGISTER_D: usize = 6;
     pub const REGISTER_E: usize = 7;
 
+    /// First bytes of [`CellState::to_bytes`].
+    const BYTES_MAGIC: &'static [u8] = b"CELL";
+
     /// Zero flag
-    pub const FLAG_Z_MASK: u8 = 0b0000_0001;
+    pub const FLAG_Z_MASK: Word = 0b0000_0001;
     /// Negative flag
-    pub const FLAG_N_MASK: u8 = 0b0000_0010;
+    pub const FLAG_N_MASK: Word = 0b0000_0010;
     /// Carry flag
-    pub const FLAG_C_MASK: u8 = 0b0000_0100;
+    pub const FLAG_C_MASK: Word = 0b0000_0100;
+
+    pub fn random(config: &VmConfig, rng: &mut impl Rng) -> Self {
+        let memory = (0..config.memory_size).map(|_| rng.gen()).collect();
+
+        let mut registers: [Word; Self::REGISTERS_COUNT] = rng.gen();
+        for register in registers.iter_mut() {
+            *register &= config.address_mode.word_mask();
+        }
 
-    pub fn random() -> Self {
-        let mut memory = [0; Self::MEMORY_SIZE];
-        for cell in memory.iter_mut() {
-            *cell = ::rand::random();
+        Self {
+            memory,
+            registers,
+            tags: Vec::new(),
+            stack: Vec::new(),
+            lineage: None,
+            provenance: None,
         }
+    }
+
+    /// [`fnv1a_hash`] of the memory, registers are ignored.
+    pub fn memory_hash(&self) -> u64 {
+        fnv1a_hash(self.memory.iter().copied())
+    }
 
-        let registers = ::rand::random();
+    /// Serialize registers and memory, protection tags and the separate stack are not saved.
+    pub fn to_bytes(&self, config: &VmConfig) -> Vec<u8> {
+        let word_bytes = config.address_mode.word_bytes() as usize;
 
-        Self { memory, registers }
+        let mut result =
+            Vec::with_capacity(9 + Self::REGISTERS_COUNT * word_bytes + self.memory.len());
+        result.extend_from_slice(Self::BYTES_MAGIC);
+        result.push(word_bytes as u8);
+        result.extend_from_slice(&(self.memory.len() as u32).to_le_bytes());
+        for register in self.registers {
+            result.extend_from_slice(&register.to_le_bytes()[..word_bytes]);
+        }
+        result.extend_from_slice(&self.memory);
+
+        result
     }
 
-    pub fn draw_to_image(&self, image: &mut Image, offset: RelativePosition) {
+    /// Deserialize the cell saved by [`CellState::to_bytes`] with the same config.
+    pub fn from_bytes(bytes: &[u8], config: &VmConfig) -> Result<Self, String> {
+        let word_bytes = config.address_mode.word_bytes() as usize;
+
+        let rest = bytes
+            .strip_prefix(Self::BYTES_MAGIC)
+            .ok_or("not a saved cell")?;
+        let [saved_word_bytes, a, b, c, d, rest @ ..] = rest else {
+            return Err("truncated header".to_owned());
+        };
+        let memory_size = u32::from_le_bytes([*a, *b, *c, *d]) as usize;
+
+        if *saved_word_bytes as usize != word_bytes || memory_size != config.memory_size {
+            return Err(format!(
+                "saved with {}-bit registers and {memory_size} bytes of memory, expected {}-bit and {}",
+                saved_word_bytes * 8,
+                word_bytes * 8,
+                config.memory_size
+            ));
+        }
+
+        let registers_size = Self::REGISTERS_COUNT * word_bytes;
+        if rest.len() != registers_size + memory_size {
+            return Err(format!(
+                "expected {} bytes after the header, got {}",
+                registers_size + memory_size,
+                rest.len()
+            ));
+        }
+
+        let (registers_bytes, memory) = rest.split_at(registers_size);
+        let mut registers = [0; Self::REGISTERS_COUNT];
+        for (register, bytes) in registers.iter_mut().zip(registers_bytes.chunks(word_bytes)) {
+            let mut word = [0; 2];
+            word[..word_bytes].copy_from_slice(bytes);
+            *register = Word::from_le_bytes(word);
+        }
+
+        Ok(Self {
+            memory: memory.to_vec(),
+            registers,
+            tags: Vec::new(),
+            stack: Vec::new(),
+            lineage: None,
+            provenance: None,
+        })
+    }
+
+    /// Returns mutable tag of the memory byte at given index, allocating tags if needed.
+    pub fn get_tag_mut(&mut self, index: usize) -> &mut MemoryTag {
+        if self.tags.is_empty() {
+            self.tags = vec![MemoryTag::default(); self.memory.len()];
+        }
+
+        &mut self.tags[index]
+    }
+
+    /// Draw registers and memory as rgb pixels, see [`VmConfig::cell_canvas_size`].
+    pub fn draw_to_image(&self, image: &mut Image, offset: RelativePosition, config: &VmConfig) {
+        let canvas_size = config.cell_canvas_size();
         let mut pixel_index = 0usize;
 
-        macro_rules! set_pixel {
-            ($r:expr, $g:expr, $b:expr) => {
-                let pos = Self::CANVAS_SIZE.index_to_coords(pixel_index) + offset;
-                image.set_pixel(pos.x, pos.y, Color::from_rgba($r, $g, $b, 255));
+        let mut draw_bytes = |bytes: &[u8]| {
+            for chunk in bytes.chunks(3) {
+                let get = |i: usize| chunk.get(i).copied().unwrap_or_default();
+
+                let pos = canvas_size.index_to_coords(pixel_index) + offset;
+                image.set_pixel(pos.x, pos.y, Color::from_rgba(get(0), get(1), get(2), 255));
                 pixel_index += 1;
-            };
-        }
+            }
+        };
 
-        set_pixel!(self.registers[0], self.registers[1], self.registers[2]);
-        set_pixel!(self.registers[3], self.registers[4], self.registers[5]);
-        set_pixel!(self.registers[6], self.registers[7], 0);
-
-        for i in 0..(Self::MEMORY_SIZE / 3 + 1) {
-            let memory_offset = i * 3;
-
-            let r = self.memory.get(memory_offset).copied().unwrap_or_default();
-            let g = self
-                .memory
-                .get(memory_offset + 1)
-                .copied()
-                .unwrap_or_default();
-            let b = self
-                .memory
-                .get(memory_offset + 2)
-                .copied()
-                .unwrap_or_default();
-
-            set_pixel!(r, g, b);
+        match config.address_mode {
+            AddressMode::Bits8 => draw_bytes(&self.registers.map(|r| r as u8)),
+            AddressMode::Bits16 => draw_bytes(self.registers.map(Word::to_le_bytes).as_flattened()),
         }
+
+        draw_bytes(&self.memory);
     }
 }
+
+#[test]
+fn test_memory_hash() {
+    use crate::Stamp;
+
+    // hashes are printed and exported, they must not change across builds
+    let config = VmConfig::default();
+    let empty = Stamp::program_cell(Vec::new(), &config).unwrap();
+    assert_eq!(empty.memory_hash(), 0x8421_ae12_6c7c_ed25);
+    let mut program = Stamp::program_cell(vec![1, 2, 3, 4], &config).unwrap();
+    assert_eq!(program.memory_hash(), 0x57e9_bd5c_7545_af0d);
+
+    program.registers[0] = 1;
+    assert_eq!(program.memory_hash(), 0x57e9_bd5c_7545_af0d);
+}
@@ -0,0 +1,714 @@
+use crate::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    ops::Range,
+};
+
+/// Where the execution can continue after an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Successor {
+    /// Instruction at the address of the cell memory.
+    Address(usize),
+    /// The program counter leaves the cell into the neighbor memory.
+    Neighbor,
+    /// The target is only known at runtime, e.g. returns and jumps through memory.
+    Unknown,
+}
+
+/// Memory written by an instruction, pushes into the wrap stack included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WriteTarget {
+    Own,
+    Neighbor,
+    /// The address is only known at runtime.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalyzedInstruction {
+    pub instruction: DisassembledInstruction,
+    /// `None` if the instruction never writes into the memory.
+    pub write: Option<WriteTarget>,
+}
+
+impl AnalyzedInstruction {
+    pub fn may_write_neighbor(&self) -> bool {
+        matches!(
+            self.write,
+            Some(WriteTarget::Neighbor | WriteTarget::Unknown)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub instructions: Vec<AnalyzedInstruction>,
+    pub successors: Vec<Successor>,
+}
+
+/// Natural loop of a back edge found by the depth-first search from the entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    pub header: usize,
+    /// Start of the block jumping back to the header.
+    pub latch: usize,
+    /// Starts of all blocks of the loop, sorted.
+    pub blocks: Vec<usize>,
+}
+
+/// Control-flow graph of the cell memory executed as code from the entry address.
+///
+/// Paths are followed with constant propagation of registers, so jumps through registers
+/// loaded with known values are resolved. Flags are not tracked, both branches of conditional
+/// instructions are assumed to be taken and calls are assumed to return. Instructions can
+/// overlap, e.g. a not taken `jz imm` continues with its immediate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub entry: usize,
+    /// Blocks sorted by the start address, the entry block is always present.
+    pub blocks: Vec<BasicBlock>,
+    pub loops: Vec<Loop>,
+    /// Bytes of the memory not covered by any reachable instruction.
+    pub unreachable: Vec<Range<usize>>,
+}
+
+/// Known register values, `None` if the value is only known at runtime.
+type Registers = [Option<Word>; CellState::REGISTERS_COUNT];
+
+impl ControlFlowGraph {
+    /// Build the graph of the cell memory. The entry must be an address inside the memory.
+    pub fn build(memory: &[u8], entry: usize, config: &VmConfig) -> Self {
+        assert!(entry < memory.len(), "entry is outside of the memory");
+
+        let analysis = Analysis { memory, config };
+
+        // registers at the start of every reachable instruction
+        let mut entry_registers: Registers = [None; CellState::REGISTERS_COUNT];
+        entry_registers[Register::ProgramCounter as usize] = Some(entry as Word);
+        let mut states = BTreeMap::from([(entry, entry_registers)]);
+
+        let mut queue = vec![entry];
+        while let Some(address) = queue.pop() {
+            let (_, paths) = analysis.step(address, &states[&address]);
+
+            for registers in paths {
+                let Successor::Address(target) = analysis.successor(&registers) else {
+                    continue;
+                };
+
+                match states.get_mut(&target) {
+                    Some(state) => {
+                        let mut changed = false;
+                        for (known, new) in state.iter_mut().zip(registers) {
+                            if known.is_some() && *known != new {
+                                *known = None;
+                                changed = true;
+                            }
+                        }
+                        if changed {
+                            queue.push(target);
+                        }
+                    }
+                    None => {
+                        states.insert(target, registers);
+                        queue.push(target);
+                    }
+                }
+            }
+        }
+
+        let mut instructions = BTreeMap::new();
+        let mut successors = BTreeMap::new();
+        for (&address, registers) in &states {
+            let (instruction, paths) = analysis.step(address, registers);
+
+            let mut targets = Vec::new();
+            for registers in paths {
+                let successor = analysis.successor(&registers);
+                if !targets.contains(&successor) {
+                    targets.push(successor);
+                }
+            }
+
+            instructions.insert(address, instruction);
+            successors.insert(address, targets);
+        }
+
+        let blocks = Self::split_blocks(entry, &instructions, &successors);
+        let loops = Self::find_loops(entry, &blocks);
+
+        let mut covered = vec![false; memory.len()];
+        for instruction in instructions.values() {
+            let range = instruction.instruction.address
+                ..(instruction.instruction.address + instruction.instruction.len).min(memory.len());
+            covered[range].fill(true);
+        }
+
+        let mut unreachable: Vec<Range<usize>> = Vec::new();
+        for address in (0..memory.len()).filter(|&address| !covered[address]) {
+            match unreachable.last_mut() {
+                Some(range) if range.end == address => range.end += 1,
+                _ => unreachable.push(address..address + 1),
+            }
+        }
+
+        Self {
+            entry,
+            blocks,
+            loops,
+            unreachable,
+        }
+    }
+
+    /// Group instructions into blocks. A block starts at the entry, at jump targets and
+    /// wherever paths merge.
+    fn split_blocks(
+        entry: usize,
+        instructions: &BTreeMap<usize, AnalyzedInstruction>,
+        successors: &BTreeMap<usize, Vec<Successor>>,
+    ) -> Vec<BasicBlock> {
+        let mut predecessors: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (&address, targets) in successors {
+            for target in targets {
+                if let Successor::Address(target) = *target {
+                    predecessors.entry(target).or_default().push(address);
+                }
+            }
+        }
+
+        let is_leader = |address: usize| {
+            if address == entry {
+                return true;
+            }
+
+            match predecessors.get(&address).map(Vec::as_slice) {
+                Some(&[predecessor]) => {
+                    let instruction = &instructions[&predecessor].instruction;
+                    successors[&predecessor].len() != 1
+                        || instruction.address + instruction.len != address
+                }
+                _ => true,
+            }
+        };
+
+        instructions
+            .keys()
+            .copied()
+            .filter(|&address| is_leader(address))
+            .map(|start| {
+                let mut block = BasicBlock {
+                    start,
+                    instructions: Vec::new(),
+                    successors: Vec::new(),
+                };
+
+                let mut address = start;
+                loop {
+                    block.instructions.push(instructions[&address]);
+
+                    match successors[&address].as_slice() {
+                        &[Successor::Address(next)] if !is_leader(next) => address = next,
+                        targets => {
+                            block.successors = targets.to_vec();
+                            break;
+                        }
+                    }
+                }
+
+                block
+            })
+            .collect()
+    }
+
+    /// Find back edges with the depth-first search and collect blocks of their loops.
+    fn find_loops(entry: usize, blocks: &[BasicBlock]) -> Vec<Loop> {
+        let index_of = |address: usize| {
+            blocks
+                .binary_search_by_key(&address, |block| block.start)
+                .ok()
+        };
+        let block_successors = |index: usize| {
+            blocks[index]
+                .successors
+                .iter()
+                .filter_map(|successor| match *successor {
+                    Successor::Address(address) => index_of(address),
+                    Successor::Neighbor | Successor::Unknown => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut predecessors = vec![Vec::new(); blocks.len()];
+        for index in 0..blocks.len() {
+            for successor in block_successors(index) {
+                predecessors[successor].push(index);
+            }
+        }
+
+        let mut loops = Vec::new();
+        let mut visited = vec![false; blocks.len()];
+        let mut on_stack = vec![false; blocks.len()];
+
+        let entry = index_of(entry).expect("entry block exists");
+        let mut stack = vec![(entry, block_successors(entry), 0)];
+        visited[entry] = true;
+        on_stack[entry] = true;
+
+        while let Some((index, successors, next)) = stack.last_mut() {
+            let Some(&successor) = successors.get(*next) else {
+                on_stack[*index] = false;
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            let index = *index;
+
+            if on_stack[successor] {
+                // natural loop: the header and everything reaching the latch without it
+                let mut body = BTreeSet::from([successor, index]);
+                let mut queue = vec![index];
+                while let Some(block) = queue.pop() {
+                    if block == successor {
+                        continue;
+                    }
+                    for &predecessor in &predecessors[block] {
+                        if body.insert(predecessor) {
+                            queue.push(predecessor);
+                        }
+                    }
+                }
+
+                loops.push(Loop {
+                    header: blocks[successor].start,
+                    latch: blocks[index].start,
+                    blocks: body.into_iter().map(|block| blocks[block].start).collect(),
+                });
+            } else if !visited[successor] {
+                visited[successor] = true;
+                on_stack[successor] = true;
+                stack.push((successor, block_successors(successor), 0));
+            }
+        }
+
+        loops.sort_by_key(|found| (found.header, found.latch));
+        loops
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = &AnalyzedInstruction> {
+        self.blocks.iter().flat_map(|block| &block.instructions)
+    }
+
+    /// Reachable instructions which can write into the neighbor memory.
+    pub fn neighbor_writes(&self) -> impl Iterator<Item = &AnalyzedInstruction> {
+        self.instructions()
+            .filter(|instruction| instruction.may_write_neighbor())
+    }
+
+    /// Export the graph in the Graphviz DOT format. Blocks writing into the neighbor are red,
+    /// back edges of loops are bold.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let node = |successor: &Successor| match successor {
+            Successor::Address(address) => format!("block_{address}"),
+            Successor::Neighbor => "neighbor".to_owned(),
+            Successor::Unknown => "unknown".to_owned(),
+        };
+
+        dot += "digraph cfg {\n";
+        dot += "    node [shape=box fontname=monospace];\n";
+        if !self.unreachable.is_empty() {
+            let ranges = self
+                .unreachable
+                .iter()
+                .map(|range| format!("{:#04x}..{:#04x}", range.start, range.end))
+                .collect::<Vec<_>>();
+            let _ = writeln!(
+                dot,
+                "    label=\"unreachable: {}\" labelloc=b;",
+                ranges.join(", ")
+            );
+        }
+        let _ = writeln!(
+            dot,
+            "    entry [shape=point];\n    entry -> block_{};",
+            self.entry
+        );
+
+        let mut used_special = BTreeSet::new();
+        for block in &self.blocks {
+            let mut label = String::new();
+            for analyzed in &block.instructions {
+                let instruction = &analyzed.instruction;
+                let _ = write!(label, "{:#04x}: {instruction}", instruction.address);
+                match analyzed.write {
+                    Some(WriteTarget::Neighbor) => label += "  ; writes neighbor",
+                    Some(WriteTarget::Unknown) => label += "  ; may write neighbor",
+                    Some(WriteTarget::Own) | None => {}
+                }
+                label += "\\l";
+            }
+
+            let color = if block
+                .instructions
+                .iter()
+                .any(AnalyzedInstruction::may_write_neighbor)
+            {
+                " color=red"
+            } else {
+                ""
+            };
+            let _ = writeln!(dot, "    block_{} [label=\"{label}\"{color}];", block.start);
+
+            for successor in &block.successors {
+                let is_back_edge = self.loops.iter().any(|found| {
+                    found.latch == block.start && *successor == Successor::Address(found.header)
+                });
+                let style = if is_back_edge { " [style=bold]" } else { "" };
+                let _ = writeln!(
+                    dot,
+                    "    block_{} -> {}{style};",
+                    block.start,
+                    node(successor)
+                );
+
+                if !matches!(successor, Successor::Address(_)) {
+                    used_special.insert(*successor);
+                }
+            }
+        }
+
+        for successor in used_special {
+            let label = match successor {
+                Successor::Neighbor => "neighbor",
+                _ => "?",
+            };
+            let _ = writeln!(
+                dot,
+                "    {} [label=\"{label}\" shape=ellipse];",
+                node(&successor)
+            );
+        }
+
+        dot += "}\n";
+        dot
+    }
+}
+
+/// Abstract execution of single instructions.
+struct Analysis<'a> {
+    memory: &'a [u8],
+    config: &'a VmConfig,
+}
+
+impl Analysis<'_> {
+    /// Decode the instruction at the address and return the registers after every path
+    /// the instruction can take.
+    fn step(&self, address: usize, registers: &Registers) -> (AnalyzedInstruction, Vec<Registers>) {
+        use Register::*;
+
+        let mask = self.config.address_mode.word_mask();
+        let word_bytes = self.config.address_mode.word_bytes();
+
+        let mut instruction = DisassembledInstruction::decode(self.memory, address, self.config);
+        if address + instruction.len > self.memory.len() {
+            // the immediate is in the neighbor memory
+            instruction.immediate = None;
+        }
+
+        let after_opcode = Some((address as Word).wrapping_add(1) & mask);
+        let after_immediate = Some((address + instruction.len) as Word & mask);
+        let immediate = instruction.immediate;
+
+        let mut regs = *registers;
+        regs[ProgramCounter as usize] = if instruction.instruction.has_immediate() {
+            after_immediate
+        } else {
+            after_opcode
+        };
+
+        let mut write = None;
+        let mut add_write = |target: WriteTarget| {
+            write = Some(match write {
+                None => target,
+                Some(previous) if previous == target => target,
+                Some(_) => WriteTarget::Unknown,
+            });
+        };
+
+        let get = |regs: &Registers, reg: Register| regs[reg as usize];
+        let set = |regs: &mut Registers, reg: Register, value: Option<Word>| {
+            regs[reg as usize] = value.map(|value| value & mask);
+        };
+        let push = |regs: &mut Registers, add_write: &mut dyn FnMut(WriteTarget)| {
+            let sp = get(regs, StackPointer);
+            let sp = match self.config.stack_mode {
+                StackMode::Wrap => {
+                    let sp = sp.map(|sp| sp.wrapping_sub(word_bytes));
+                    add_write(self.write_target(sp.map(|sp| sp & mask), word_bytes as usize));
+                    sp
+                }
+                StackMode::Separate { size } => {
+                    sp.map(|sp| ((sp as usize % size + size - 1) % size) as Word)
+                }
+                // the stack pointer stays if the push traps
+                StackMode::Trap { .. } => None,
+            };
+            set(regs, StackPointer, sp);
+        };
+        let pop = |regs: &mut Registers| {
+            let sp = match self.config.stack_mode {
+                StackMode::Wrap => get(regs, StackPointer).map(|sp| sp.wrapping_add(word_bytes)),
+                StackMode::Separate { .. } | StackMode::Trap { .. } => None,
+            };
+            set(regs, StackPointer, sp);
+        };
+        let binary = |regs: &mut Registers, reg: Register, op: fn(Word, Word) -> Word| {
+            let value = get(regs, Accumulator).zip(get(regs, reg));
+            set(regs, Flags, None);
+            set(regs, Accumulator, value.map(|(a, b)| op(a, b)));
+        };
+        // unary operations set flags before writing the result, so `[f]` is the new flags
+        let unary = |regs: &mut Registers,
+                     target: UnaryTarget,
+                     add_write: &mut dyn FnMut(WriteTarget),
+                     op: fn(Word) -> Word| {
+            match target {
+                UnaryTarget::Reg(reg) => {
+                    let value = get(regs, reg).map(op);
+                    set(regs, Flags, None);
+                    set(regs, reg, value);
+                }
+                UnaryTarget::AtReg(reg) => {
+                    set(regs, Flags, None);
+                    add_write(self.write_target(get(regs, reg), 1));
+                }
+            }
+        };
+
+        let mut paths = Vec::new();
+        match instruction.instruction {
+            Instruction::Nop(_) | Instruction::Replicate(_) => {}
+            Instruction::Protect(_) | Instruction::Unprotect(_) => set(&mut regs, Flags, None),
+            Instruction::Load(load) => match load {
+                InstructionLoad::a_reg(reg) => {
+                    let value = get(&regs, reg);
+                    set(&mut regs, Accumulator, value);
+                }
+                InstructionLoad::atA_reg(_) => {
+                    add_write(self.write_target(get(&regs, Accumulator), 1));
+                }
+                InstructionLoad::reg_atA(reg) => set(&mut regs, reg, None),
+                InstructionLoad::reg_a(reg) => {
+                    let value = get(&regs, Accumulator);
+                    set(&mut regs, reg, value);
+                }
+                InstructionLoad::a_byte => set(&mut regs, Accumulator, immediate),
+            },
+            Instruction::Add(InstructionAdd::a_reg(reg)) => {
+                binary(&mut regs, reg, Word::wrapping_add);
+            }
+            Instruction::Sub(InstructionSub::a_reg(reg)) => {
+                binary(&mut regs, reg, Word::wrapping_sub);
+            }
+            Instruction::And(InstructionAnd::a_reg(reg)) => binary(&mut regs, reg, |a, b| a & b),
+            Instruction::Or(InstructionOr::a_reg(reg)) => binary(&mut regs, reg, |a, b| a | b),
+            Instruction::Xor(InstructionXor::a_reg(reg)) => binary(&mut regs, reg, |a, b| a ^ b),
+            Instruction::Add(InstructionAdd::a_atReg(_))
+            | Instruction::Sub(InstructionSub::a_atReg(_))
+            | Instruction::And(InstructionAnd::a_atReg(_))
+            | Instruction::Or(InstructionOr::a_atReg(_))
+            | Instruction::Xor(InstructionXor::a_atReg(_)) => {
+                set(&mut regs, Flags, None);
+                set(&mut regs, Accumulator, None);
+            }
+            Instruction::Not(not) => {
+                let target = match not {
+                    InstructionNot::reg(reg) => UnaryTarget::Reg(reg),
+                    InstructionNot::atReg(reg) => UnaryTarget::AtReg(reg),
+                };
+                unary(&mut regs, target, &mut add_write, |value| !value);
+            }
+            Instruction::Inc(inc) => {
+                let target = match inc {
+                    InstructionInc::reg(reg) => UnaryTarget::Reg(reg),
+                    InstructionInc::atReg(reg) => UnaryTarget::AtReg(reg),
+                };
+                unary(&mut regs, target, &mut add_write, |value| {
+                    value.wrapping_add(1)
+                });
+            }
+            Instruction::Dec(dec) => {
+                let target = match dec {
+                    InstructionDec::reg(reg) => UnaryTarget::Reg(reg),
+                    InstructionDec::atReg(reg) => UnaryTarget::AtReg(reg),
+                };
+                unary(&mut regs, target, &mut add_write, |value| {
+                    value.wrapping_sub(1)
+                });
+            }
+            Instruction::LeftShift(shift) => {
+                let target = match shift {
+                    InstructionLeftShift::reg(reg) => UnaryTarget::Reg(reg),
+                    InstructionLeftShift::atReg(reg) => UnaryTarget::AtReg(reg),
+                };
+                unary(&mut regs, target, &mut add_write, |value| {
+                    value.wrapping_shl(1)
+                });
+            }
+            Instruction::RightShift(shift) => {
+                let target = match shift {
+                    InstructionRightShift::reg(reg) => UnaryTarget::Reg(reg),
+                    InstructionRightShift::atReg(reg) => UnaryTarget::AtReg(reg),
+                };
+                unary(&mut regs, target, &mut add_write, |value| {
+                    value.wrapping_shr(1)
+                });
+            }
+            Instruction::Jmp(jump) => {
+                let (is_conditional, target) = match jump {
+                    InstructionJump::byte { if_z } => (if_z, immediate),
+                    InstructionJump::reg(reg) => (false, get(&regs, reg)),
+                    InstructionJump::ifZ_reg(reg) => (true, get(&regs, reg)),
+                    InstructionJump::atReg(_) => (false, None),
+                    InstructionJump::ifZ_atReg(_) => (true, None),
+                };
+
+                if is_conditional {
+                    // not taken jumps don't consume the immediate
+                    let mut not_taken = *registers;
+                    not_taken[ProgramCounter as usize] = after_opcode;
+                    paths.push(not_taken);
+                }
+
+                push(&mut regs, &mut add_write);
+                set(&mut regs, ProgramCounter, target);
+            }
+            Instruction::Call(call) => {
+                let (is_conditional, target) = match call {
+                    InstructionCall::byte { if_z } => (if_z, immediate),
+                    InstructionCall::reg(reg) => (false, get(&regs, reg)),
+                    InstructionCall::ifZ_reg(reg) => (true, get(&regs, reg)),
+                };
+
+                if is_conditional {
+                    let mut not_taken = *registers;
+                    not_taken[ProgramCounter as usize] = after_opcode;
+                    paths.push(not_taken);
+                }
+
+                // the callee returns with unknown registers
+                let mut returned = [None; CellState::REGISTERS_COUNT];
+                returned[ProgramCounter as usize] = get(&regs, ProgramCounter);
+                paths.push(returned);
+
+                push(&mut regs, &mut add_write);
+                set(&mut regs, ProgramCounter, target);
+            }
+            Instruction::Ret(InstructionRet { if_z }) => {
+                if if_z {
+                    paths.push(regs);
+                }
+
+                pop(&mut regs);
+                set(&mut regs, ProgramCounter, None);
+            }
+            Instruction::Push(_) => push(&mut regs, &mut add_write),
+            Instruction::Pop(InstructionPop::reg(reg)) => {
+                pop(&mut regs);
+                set(&mut regs, reg, None);
+            }
+            Instruction::Pop(InstructionPop::atReg(reg)) => {
+                pop(&mut regs);
+                add_write(self.write_target(get(&regs, reg), 1));
+            }
+            Instruction::Compare(_) => set(&mut regs, Flags, None),
+            Instruction::Random(InstructionRandom::reg(reg)) => set(&mut regs, reg, None),
+            Instruction::Sense(_) => set(&mut regs, Accumulator, None),
+        }
+        paths.push(regs);
+
+        let instruction = AnalyzedInstruction { instruction, write };
+        (instruction, paths)
+    }
+
+    /// Where the program counter of the registers points to.
+    fn successor(&self, registers: &Registers) -> Successor {
+        match registers[Register::ProgramCounter as usize] {
+            None => Successor::Unknown,
+            Some(pc) => {
+                let address = pc as usize % self.config.address_space();
+                if address < self.memory.len() {
+                    Successor::Address(address)
+                } else {
+                    Successor::Neighbor
+                }
+            }
+        }
+    }
+
+    /// Classify the write of the given number of bytes at the address.
+    fn write_target(&self, address: Option<Word>, len: usize) -> WriteTarget {
+        let Some(address) = address else {
+            return WriteTarget::Unknown;
+        };
+
+        let mask = self.config.address_mode.word_mask();
+        let is_neighbor = (0..len as Word).any(|offset| {
+            let address = (address.wrapping_add(offset) & mask) as usize;
+            address % self.config.address_space() >= self.config.memory_size
+        });
+
+        if is_neighbor {
+            WriteTarget::Neighbor
+        } else {
+            WriteTarget::Own
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum UnaryTarget {
+    Reg(Register),
+    AtReg(Register),
+}
+
+#[test]
+fn test_control_flow_graph() {
+    let config = VmConfig::default();
+    let copy_loop = Replicator::find("copy_loop").unwrap();
+    let program = copy_loop.program(&config).unwrap();
+    let mut memory = program.clone();
+    memory.resize(config.memory_size, 0);
+
+    let graph = ControlFlowGraph::build(&memory, 0, &config);
+
+    // `start`, `loop` and the `jmp loop` after the not taken `jz start`
+    assert_eq!(
+        graph
+            .blocks
+            .iter()
+            .map(|block| block.start)
+            .collect::<Vec<_>>(),
+        [0x00, 0x0a, 0x14]
+    );
+    assert_eq!(graph.unreachable, vec![program.len()..config.memory_size]);
+    assert_eq!(graph.loops.len(), 2);
+    assert!(graph.loops.iter().all(|found| found.blocks.contains(&0x0a)));
+    assert!(
+        graph
+            .neighbor_writes()
+            .any(|write| write.instruction.instruction
+                == InstructionLoad::atA_reg(Register::C).into())
+    );
+
+    let memory = assemble("ld a, memory_size\nld [a], b\nret", &config).unwrap();
+    let graph = ControlFlowGraph::build(&memory, 0, &config);
+    assert_eq!(graph.blocks.len(), 1);
+    assert_eq!(graph.blocks[0].successors, [Successor::Unknown]);
+    assert_eq!(
+        graph.blocks[0].instructions[1].write,
+        Some(WriteTarget::Neighbor)
+    );
+}
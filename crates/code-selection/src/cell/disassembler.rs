@@ -0,0 +1,217 @@
+use crate::*;
+use std::{fmt, str::FromStr};
+
+/// Single decoded instruction together with its immediate value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    pub address: usize,
+    pub opcode: u8,
+    pub instruction: Instruction,
+    /// Immediate value following the opcode. `None` if the instruction has no immediate or
+    /// it was cut off by the sync address of [`disassemble`].
+    pub immediate: Option<Word>,
+    /// Number of bytes occupied by the instruction.
+    pub len: usize,
+}
+
+impl DisassembledInstruction {
+    /// Decode instruction at the given address, addresses wrap around the memory length.
+    pub fn decode(memory: &[u8], address: usize, config: &VmConfig) -> Self {
+        let read = |offset: usize| memory[(address + offset) % memory.len()];
+
+        let opcode = read(0);
+        let instruction = Instruction::decode(opcode, config.opcode_revision);
+
+        let (immediate, len) = if instruction.has_immediate() {
+            let immediate = match config.address_mode {
+                AddressMode::Bits8 => read(1) as Word,
+                AddressMode::Bits16 => Word::from_le_bytes([read(1), read(2)]),
+            };
+            (
+                Some(immediate),
+                1 + config.address_mode.word_bytes() as usize,
+            )
+        } else {
+            (None, 1)
+        };
+
+        Self {
+            address,
+            opcode,
+            instruction,
+            immediate,
+            len,
+        }
+    }
+}
+
+impl DisassembledInstruction {
+    /// Check if the address is occupied by the instruction.
+    pub fn contains(&self, address: usize) -> bool {
+        (self.address..self.address + self.len).contains(&address)
+    }
+}
+
+/// Linear sweep disassembly of the whole memory. Instructions never overlap the sync address
+/// (usually the program counter), an instruction which immediate would cover it is cut.
+pub fn disassemble(
+    memory: &[u8],
+    config: &VmConfig,
+    sync_address: Option<usize>,
+) -> Vec<DisassembledInstruction> {
+    let mut result = Vec::new();
+    let mut address = 0;
+
+    while address < memory.len() {
+        let mut instruction = DisassembledInstruction::decode(memory, address, config);
+
+        if let Some(sync_address) = sync_address {
+            if address < sync_address && sync_address < address + instruction.len {
+                instruction.immediate = None;
+                instruction.len = sync_address - address;
+            }
+        }
+
+        address += instruction.len;
+        result.push(instruction);
+    }
+
+    result
+}
+
+impl Instruction {
+    /// Returns true if the opcode is followed by register sized immediate value.
+    pub fn has_immediate(&self) -> bool {
+        matches!(
+            self,
+            Self::Load(InstructionLoad::a_byte)
+                | Self::Jmp(InstructionJump::byte { .. })
+                | Self::Call(InstructionCall::byte { .. })
+                | Self::Compare(InstructionCompare::a_byte | InstructionCompare::atA_byte)
+        )
+    }
+
+    /// Write assembly of the instruction, missing immediate is written as `?`.
+    pub fn write_asm(&self, f: &mut impl fmt::Write, immediate: Option<Word>) -> fmt::Result {
+        let imm = match immediate {
+            Some(value) => format!("{value:#04x}"),
+            None => "?".to_owned(),
+        };
+
+        match *self {
+            Self::Nop(_) => write!(f, "nop"),
+            Self::Load(load) => match load {
+                InstructionLoad::a_reg(reg) => write!(f, "ld a, {reg}"),
+                InstructionLoad::reg_atA(reg) => write!(f, "ld {reg}, [a]"),
+                InstructionLoad::reg_a(reg) => write!(f, "ld {reg}, a"),
+                InstructionLoad::atA_reg(reg) => write!(f, "ld [a], {reg}"),
+                InstructionLoad::a_byte => write!(f, "ld a, {imm}"),
+            },
+            Self::Add(InstructionAdd::a_reg(reg)) => write!(f, "add a, {reg}"),
+            Self::Add(InstructionAdd::a_atReg(reg)) => write!(f, "add a, [{reg}]"),
+            Self::Sub(InstructionSub::a_reg(reg)) => write!(f, "sub a, {reg}"),
+            Self::Sub(InstructionSub::a_atReg(reg)) => write!(f, "sub a, [{reg}]"),
+            Self::And(InstructionAnd::a_reg(reg)) => write!(f, "and a, {reg}"),
+            Self::And(InstructionAnd::a_atReg(reg)) => write!(f, "and a, [{reg}]"),
+            Self::Or(InstructionOr::a_reg(reg)) => write!(f, "or a, {reg}"),
+            Self::Or(InstructionOr::a_atReg(reg)) => write!(f, "or a, [{reg}]"),
+            Self::Xor(InstructionXor::a_reg(reg)) => write!(f, "xor a, {reg}"),
+            Self::Xor(InstructionXor::a_atReg(reg)) => write!(f, "xor a, [{reg}]"),
+            Self::Not(InstructionNot::reg(reg)) => write!(f, "not {reg}"),
+            Self::Not(InstructionNot::atReg(reg)) => write!(f, "not [{reg}]"),
+            Self::Inc(InstructionInc::reg(reg)) => write!(f, "inc {reg}"),
+            Self::Inc(InstructionInc::atReg(reg)) => write!(f, "inc [{reg}]"),
+            Self::Dec(InstructionDec::reg(reg)) => write!(f, "dec {reg}"),
+            Self::Dec(InstructionDec::atReg(reg)) => write!(f, "dec [{reg}]"),
+            Self::Jmp(jump) => match jump {
+                InstructionJump::reg(reg) => write!(f, "jmp {reg}"),
+                InstructionJump::atReg(reg) => write!(f, "jmp [{reg}]"),
+                InstructionJump::ifZ_reg(reg) => write!(f, "jz {reg}"),
+                InstructionJump::ifZ_atReg(reg) => write!(f, "jz [{reg}]"),
+                InstructionJump::byte { if_z: false } => write!(f, "jmp {imm}"),
+                InstructionJump::byte { if_z: true } => write!(f, "jz {imm}"),
+            },
+            Self::Push(InstructionPush::reg(reg)) => write!(f, "push {reg}"),
+            Self::Push(InstructionPush::atReg(reg)) => write!(f, "push [{reg}]"),
+            Self::Pop(InstructionPop::reg(reg)) => write!(f, "pop {reg}"),
+            Self::Pop(InstructionPop::atReg(reg)) => write!(f, "pop [{reg}]"),
+            Self::Call(call) => match call {
+                InstructionCall::reg(reg) => write!(f, "call {reg}"),
+                InstructionCall::ifZ_reg(reg) => write!(f, "callz {reg}"),
+                InstructionCall::byte { if_z: false } => write!(f, "call {imm}"),
+                InstructionCall::byte { if_z: true } => write!(f, "callz {imm}"),
+            },
+            Self::Ret(InstructionRet { if_z: false }) => write!(f, "ret"),
+            Self::Ret(InstructionRet { if_z: true }) => write!(f, "retz"),
+            Self::LeftShift(InstructionLeftShift::reg(reg)) => write!(f, "shl {reg}"),
+            Self::LeftShift(InstructionLeftShift::atReg(reg)) => write!(f, "shl [{reg}]"),
+            Self::RightShift(InstructionRightShift::reg(reg)) => write!(f, "shr {reg}"),
+            Self::RightShift(InstructionRightShift::atReg(reg)) => write!(f, "shr [{reg}]"),
+            Self::Compare(compare) => match compare {
+                InstructionCompare::a_reg(reg) => write!(f, "cmp a, {reg}"),
+                InstructionCompare::a_byte => write!(f, "cmp a, {imm}"),
+                InstructionCompare::atA_byte => write!(f, "cmp [a], {imm}"),
+            },
+            Self::Replicate(_) => write!(f, "rep"),
+            Self::Protect(_) => write!(f, "prot [a]"),
+            Self::Unprotect(_) => write!(f, "unprot [a]"),
+            Self::Random(InstructionRandom::reg(reg)) => write!(f, "rnd {reg}"),
+            Self::Sense(sense) => match sense {
+                InstructionSense::direction => write!(f, "sense dir"),
+                InstructionSense::x => write!(f, "sense x"),
+                InstructionSense::y => write!(f, "sense y"),
+                InstructionSense::tick => write!(f, "sense tick"),
+                InstructionSense::role => write!(f, "sense role"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_asm(f, None)
+    }
+}
+
+impl fmt::Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.instruction.write_asm(f, self.immediate)
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Accumulator => "a",
+            Self::Flags => "f",
+            Self::ProgramCounter => "pc",
+            Self::StackPointer => "sp",
+            Self::B => "b",
+            Self::C => "c",
+            Self::D => "d",
+            Self::E => "e",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Register {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let register = match name {
+            "a" => Self::Accumulator,
+            "f" => Self::Flags,
+            "pc" => Self::ProgramCounter,
+            "sp" => Self::StackPointer,
+            "b" => Self::B,
+            "c" => Self::C,
+            "d" => Self::D,
+            "e" => Self::E,
+            _ => return Err(format!("unknown register `{name}`")),
+        };
+
+        Ok(register)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::*;
+use ::rand::Rng;
 use enum_dispatch::enum_dispatch;
 
 #[enum_dispatch]
@@ -23,6 +24,152 @@ pub enum Instruction {
     RightShift(InstructionRightShift),
     Compare(InstructionCompare),
     Replicate(InstructionReplicate),
+    Protect(InstructionProtect),
+    Unprotect(InstructionUnprotect),
+    Random(InstructionRandom),
+    Sense(InstructionSense),
+}
+
+impl Instruction {
+    pub const VARIANT_COUNT: usize = 23;
+
+    /// Names of the variants, indexed by [`Instruction::variant_index`].
+    pub const VARIANT_NAMES: [&str; Self::VARIANT_COUNT] = [
+        "Nop",
+        "Load",
+        "Add",
+        "Sub",
+        "And",
+        "Or",
+        "Xor",
+        "Not",
+        "Inc",
+        "Dec",
+        "Jmp",
+        "Push",
+        "Pop",
+        "Call",
+        "Ret",
+        "LeftShift",
+        "RightShift",
+        "Compare",
+        "Replicate",
+        "Protect",
+        "Unprotect",
+        "Random",
+        "Sense",
+    ];
+
+    #[inline(always)]
+    pub const fn variant_index(&self) -> usize {
+        match self {
+            Self::Nop(_) => 0,
+            Self::Load(_) => 1,
+            Self::Add(_) => 2,
+            Self::Sub(_) => 3,
+            Self::And(_) => 4,
+            Self::Or(_) => 5,
+            Self::Xor(_) => 6,
+            Self::Not(_) => 7,
+            Self::Inc(_) => 8,
+            Self::Dec(_) => 9,
+            Self::Jmp(_) => 10,
+            Self::Push(_) => 11,
+            Self::Pop(_) => 12,
+            Self::Call(_) => 13,
+            Self::Ret(_) => 14,
+            Self::LeftShift(_) => 15,
+            Self::RightShift(_) => 16,
+            Self::Compare(_) => 17,
+            Self::Replicate(_) => 18,
+            Self::Protect(_) => 19,
+            Self::Unprotect(_) => 20,
+            Self::Random(_) => 21,
+            Self::Sense(_) => 22,
+        }
+    }
+
+    /// Decode the instruction from the opcode using given revision of the opcode map.
+    pub fn decode(opcode: u8, revision: OpcodeRevision) -> Self {
+        #[allow(clippy::unusual_byte_groupings)]
+        match opcode {
+            0b0000_0000 => InstructionNop.into(),
+
+            // skip acc register (0x00 is nop) because `ld a a` does not make sense
+            0b00000_001..=0b00000_111 => InstructionLoad::a_reg(opcode.into()).into(),
+            0b00001_000..=0b00001_111 => InstructionLoad::atA_reg(opcode.into()).into(),
+            0b00010_000..=0b00010_111 => InstructionLoad::reg_atA(opcode.into()).into(),
+            0b00011_000 => InstructionLoad::a_byte.into(),
+            // same as for InstructionLoad::a_reg (0b00000_001..=0b00000_111)
+            0b00011_001..=0b00011_111 => InstructionLoad::reg_a(opcode.into()).into(),
+
+            0b00100_000..=0b00100_111 => InstructionAdd::a_reg(opcode.into()).into(),
+            0b00101_000..=0b00101_111 => InstructionAdd::a_atReg(opcode.into()).into(),
+
+            0b00110_000..=0b00110_111 => InstructionSub::a_reg(opcode.into()).into(),
+            0b00111_000..=0b00111_111 => InstructionSub::a_atReg(opcode.into()).into(),
+
+            0b01000_000..=0b01000_111 => InstructionAnd::a_reg(opcode.into()).into(),
+            0b01001_000..=0b01001_111 => InstructionAnd::a_atReg(opcode.into()).into(),
+
+            0b01010_000..=0b01010_111 => InstructionOr::a_reg(opcode.into()).into(),
+            0b01011_000..=0b01011_111 => InstructionOr::a_atReg(opcode.into()).into(),
+
+            0b01100_000..=0b01100_111 => InstructionXor::a_reg(opcode.into()).into(),
+            0b01101_000..=0b01101_111 => InstructionXor::a_atReg(opcode.into()).into(),
+
+            0b01110_000..=0b01110_111 => InstructionNot::reg(opcode.into()).into(),
+            0b01111_000..=0b01111_111 => InstructionNot::atReg(opcode.into()).into(),
+
+            0b10000_000..=0b10000_111 => InstructionJump::reg(opcode.into()).into(),
+            0b10001_000..=0b10001_111 => InstructionJump::atReg(opcode.into()).into(),
+            0b10010_000..=0b10010_111 => InstructionJump::ifZ_reg(opcode.into()).into(),
+            0b10011_000..=0b10011_111 => InstructionJump::ifZ_atReg(opcode.into()).into(),
+
+            0b10100_000..=0b10100_111 => InstructionPush::reg(opcode.into()).into(),
+            0b10101_000..=0b10101_111 => InstructionPush::atReg(opcode.into()).into(),
+
+            0b10110_000..=0b10110_111 => InstructionPop::reg(opcode.into()).into(),
+            0b10111_000..=0b10111_111 => InstructionPop::atReg(opcode.into()).into(),
+
+            0b11000_000 if revision >= OpcodeRevision::V1 => InstructionProtect.into(),
+            0b11000_001 if revision >= OpcodeRevision::V1 => InstructionUnprotect.into(),
+
+            0b11000_010 if revision >= OpcodeRevision::V3 => InstructionSense::direction.into(),
+            0b11000_011 if revision >= OpcodeRevision::V3 => InstructionSense::x.into(),
+            0b11000_100 if revision >= OpcodeRevision::V3 => InstructionSense::y.into(),
+            0b11000_101 if revision >= OpcodeRevision::V3 => InstructionSense::tick.into(),
+            0b11000_110 if revision >= OpcodeRevision::V3 => InstructionSense::role.into(),
+
+            0b11001_000..=0b11001_111 if revision >= OpcodeRevision::V2 => {
+                InstructionRandom::reg(opcode.into()).into()
+            }
+
+            0b11000_000..=0b11000_111 => InstructionCall::reg(opcode.into()).into(),
+            0b11001_000..=0b11001_111 => InstructionCall::ifZ_reg(opcode.into()).into(),
+
+            0b11010_000..=0b11010_111 => InstructionLeftShift::reg(opcode.into()).into(),
+            0b11011_000..=0b11011_111 => InstructionLeftShift::atReg(opcode.into()).into(),
+
+            0b11100_000..=0b11100_111 => InstructionRightShift::reg(opcode.into()).into(),
+            0b11101_000..=0b11101_111 => InstructionRightShift::atReg(opcode.into()).into(),
+
+            0b11110_000 => InstructionCompare::a_byte.into(),
+            0b11110_001..=0b11110_111 => InstructionCompare::a_reg(opcode.into()).into(),
+            0b11111_000 => InstructionCompare::atA_byte.into(),
+
+            0b11111_001 => InstructionReplicate.into(),
+
+            0b11111_010 => InstructionJump::byte { if_z: false }.into(),
+            0b11111_011 => InstructionJump::byte { if_z: true }.into(),
+
+            0b11111_100 => InstructionCall::byte { if_z: true }.into(),
+            0b11111_101 => InstructionCall::byte { if_z: false }.into(),
+
+            0b111_11_110 => InstructionRet { if_z: false }.into(),
+            0b111_11_111 => InstructionRet { if_z: true }.into(),
+        }
+    }
 }
 
 #[enum_dispatch(Instruction)]
@@ -30,7 +177,7 @@ pub trait ProcessInstruction {
     fn process(&self, _state: &mut CellPair) {}
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Register {
     Accumulator,
     Flags,
@@ -74,7 +221,7 @@ pub enum InstructionLoad {
     reg_a(Register),
     /// Load value from register to memory at address pointed by accumulator.
     atA_reg(Register),
-    /// read byte at pc, increment pc and load byte to accumulator.
+    /// read immediate value at pc, advance pc and load the value to accumulator.
     a_byte,
 }
 
@@ -82,13 +229,26 @@ impl ProcessInstruction for InstructionLoad {
     fn process(&self, state: &mut CellPair) {
         match *self {
             Self::a_byte => {
-                let value = state.advance_pc();
+                state.carry_memory(state.get_reg_pc());
+                let value = state.advance_pc_word();
                 state.set_reg_acc(value);
             }
-            Self::a_reg(register) => state.set_reg_acc(state.get_reg(register)),
-            Self::atA_reg(register) => state.set_memory_at_acc(state.get_reg(register)),
-            Self::reg_a(register) => state.set_reg(register, state.get_reg_acc()),
-            Self::reg_atA(register) => state.set_reg(register, state.get_memory_at_acc()),
+            Self::a_reg(register) => {
+                state.carry_register(register);
+                state.set_reg_acc(state.get_reg(register));
+            }
+            Self::atA_reg(register) => {
+                state.carry_register(register);
+                state.set_memory_at_acc(state.get_reg(register) as u8);
+            }
+            Self::reg_a(register) => {
+                state.carry_register(Register::Accumulator);
+                state.set_reg(register, state.get_reg_acc());
+            }
+            Self::reg_atA(register) => {
+                state.carry_memory(state.get_reg_acc());
+                state.set_reg(register, state.get_memory_at_acc() as Word);
+            }
         }
     }
 }
@@ -105,16 +265,16 @@ pub enum InstructionAdd {
 impl ProcessInstruction for InstructionAdd {
     fn process(&self, state: &mut CellPair) {
         let value = match *self {
-            Self::a_atReg(reg) => state.get_memory_at_reg(reg),
+            Self::a_atReg(reg) => state.get_memory_at_reg(reg) as Word,
             Self::a_reg(reg) => state.get_reg(reg),
         };
 
         let acc = state.get_reg_acc();
-        let result = acc.wrapping_add(value);
+        let result = acc.wrapping_add(value) & state.word_mask();
 
         state.set_flag_z(result == 0);
         state.set_flag_n(false);
-        state.set_flag_c(value as u16 + acc as u16 > 0xFF);
+        state.set_flag_c(value as u32 + acc as u32 > state.word_mask() as u32);
 
         state.set_reg_acc(result);
     }
@@ -132,12 +292,12 @@ pub enum InstructionSub {
 impl ProcessInstruction for InstructionSub {
     fn process(&self, state: &mut CellPair) {
         let value = match *self {
-            Self::a_atReg(reg) => state.get_memory_at_reg(reg),
+            Self::a_atReg(reg) => state.get_memory_at_reg(reg) as Word,
             Self::a_reg(reg) => state.get_reg(reg),
         };
 
         let acc = state.get_reg_acc();
-        let result = acc.wrapping_sub(value);
+        let result = acc.wrapping_sub(value) & state.word_mask();
 
         state.set_flag_z(result == 0);
         state.set_flag_n(true);
@@ -160,7 +320,7 @@ impl ProcessInstruction for InstructionAnd {
     fn process(&self, state: &mut CellPair) {
         let v = match *self {
             Self::a_reg(reg) => state.get_reg(reg),
-            Self::a_atReg(reg) => state.get_memory_at_reg(reg),
+            Self::a_atReg(reg) => state.get_memory_at_reg(reg) as Word,
         };
 
         let acc = state.get_reg_acc();
@@ -187,7 +347,7 @@ impl ProcessInstruction for InstructionOr {
     fn process(&self, state: &mut CellPair) {
         let v = match *self {
             Self::a_reg(reg) => state.get_reg(reg),
-            Self::a_atReg(reg) => state.get_memory_at_reg(reg),
+            Self::a_atReg(reg) => state.get_memory_at_reg(reg) as Word,
         };
 
         let acc = state.get_reg_acc();
@@ -214,7 +374,7 @@ impl ProcessInstruction for InstructionXor {
     fn process(&self, state: &mut CellPair) {
         let v = match *self {
             Self::a_reg(reg) => state.get_reg(reg),
-            Self::a_atReg(reg) => state.get_memory_at_reg(reg),
+            Self::a_atReg(reg) => state.get_memory_at_reg(reg) as Word,
         };
 
         let acc = state.get_reg_acc();
@@ -239,12 +399,12 @@ pub enum InstructionNot {
 
 impl ProcessInstruction for InstructionNot {
     fn process(&self, state: &mut CellPair) {
-        let value = match *self {
-            Self::reg(reg) => state.get_reg(reg),
-            Self::atReg(reg) => state.get_memory_at_reg(reg),
+        let (value, mask) = match *self {
+            Self::reg(reg) => (state.get_reg(reg), state.word_mask()),
+            Self::atReg(reg) => (state.get_memory_at_reg(reg) as Word, u8::MAX as Word),
         };
 
-        let result = !value;
+        let result = !value & mask;
 
         state.set_flag_z(result == 0);
         state.set_flag_n(false);
@@ -252,7 +412,7 @@ impl ProcessInstruction for InstructionNot {
 
         match *self {
             Self::reg(reg) => state.set_reg(reg, result),
-            Self::atReg(reg) => state.set_memory_at_reg(reg, result),
+            Self::atReg(reg) => state.set_memory_at_reg(reg, result as u8),
         }
     }
 }
@@ -268,12 +428,12 @@ pub enum InstructionInc {
 
 impl ProcessInstruction for InstructionInc {
     fn process(&self, state: &mut CellPair) {
-        let value = match *self {
-            Self::reg(reg) => state.get_reg(reg),
-            Self::atReg(reg) => state.get_memory_at_reg(reg),
+        let (value, mask) = match *self {
+            Self::reg(reg) => (state.get_reg(reg), state.word_mask()),
+            Self::atReg(reg) => (state.get_memory_at_reg(reg) as Word, u8::MAX as Word),
         };
 
-        let result = value.wrapping_add(1);
+        let result = value.wrapping_add(1) & mask;
 
         state.set_flag_z(result == 0);
         state.set_flag_n(false);
@@ -284,7 +444,7 @@ impl ProcessInstruction for InstructionInc {
                 state.set_reg(reg, result);
             }
             Self::atReg(reg) => {
-                state.set_memory_at_reg(reg, result);
+                state.set_memory_at_reg(reg, result as u8);
             }
         }
     }
@@ -301,12 +461,12 @@ pub enum InstructionDec {
 
 impl ProcessInstruction for InstructionDec {
     fn process(&self, state: &mut CellPair) {
-        let value = match *self {
-            Self::reg(reg) => state.get_reg(reg),
-            Self::atReg(reg) => state.get_memory_at_reg(reg),
+        let (value, mask) = match *self {
+            Self::reg(reg) => (state.get_reg(reg), state.word_mask()),
+            Self::atReg(reg) => (state.get_memory_at_reg(reg) as Word, u8::MAX as Word),
         };
 
-        let result = value.wrapping_sub(1);
+        let result = value.wrapping_sub(1) & mask;
 
         state.set_flag_z(result == 0);
         state.set_flag_n(true);
@@ -317,7 +477,7 @@ impl ProcessInstruction for InstructionDec {
                 state.set_reg(reg, result);
             }
             Self::atReg(reg) => {
-                state.set_memory_at_reg(reg, result);
+                state.set_memory_at_reg(reg, result as u8);
             }
         }
     }
@@ -328,11 +488,11 @@ impl ProcessInstruction for InstructionDec {
 pub enum InstructionJump {
     /// pc = $reg
     reg(Register),
-    /// pc = [$reg]
+    /// pc = [$reg], reads register sized value
     atReg(Register),
     /// if z { pc = $reg }
     ifZ_reg(Register),
-    /// if z { pc = [$reg] }
+    /// if z { pc = [$reg] }, reads register sized value
     ifZ_atReg(Register),
 
     byte {
@@ -353,19 +513,22 @@ impl ProcessInstruction for InstructionJump {
 
         match *self {
             Self::byte { .. } => {
-                let address = state.advance_pc();
-                state.push_to_stack(state.get_reg_pc());
-                state.set_reg_pc(address);
+                let address = state.advance_pc_word();
+                if state.push_to_stack(state.get_reg_pc()) {
+                    state.set_reg_pc(address);
+                }
             }
             Self::reg(reg) | Self::ifZ_reg(reg) => {
                 let address = state.get_reg(reg);
-                state.push_to_stack(state.get_reg_pc());
-                state.set_reg_pc(address);
+                if state.push_to_stack(state.get_reg_pc()) {
+                    state.set_reg_pc(address);
+                }
             }
             Self::atReg(reg) | Self::ifZ_atReg(reg) => {
-                let address = state.get_memory_at_reg(reg);
-                state.push_to_stack(state.get_reg_pc());
-                state.set_reg_pc(address);
+                let address = state.get_memory_word_at_reg(reg);
+                if state.push_to_stack(state.get_reg_pc()) {
+                    state.set_reg_pc(address);
+                }
             }
         }
     }
@@ -385,10 +548,12 @@ impl ProcessInstruction for InstructionPush {
         match *self {
             Self::reg(reg) => {
                 let value = state.get_reg(reg);
+                state.carry_register(reg);
                 state.push_to_stack(value);
             }
             Self::atReg(reg) => {
-                let value = state.get_memory_at_reg(reg);
+                let value = state.get_memory_at_reg(reg) as Word;
+                state.carry_memory(state.get_reg(reg));
                 state.push_to_stack(value);
             }
         }
@@ -408,12 +573,16 @@ impl ProcessInstruction for InstructionPop {
     fn process(&self, state: &mut CellPair) {
         match *self {
             Self::reg(reg) => {
-                let value = state.pop_from_stack();
+                let Some(value) = state.pop_from_stack() else {
+                    return;
+                };
                 state.set_reg(reg, value);
             }
             Self::atReg(reg) => {
-                let value = state.pop_from_stack();
-                state.set_memory_at_reg(reg, value);
+                let Some(value) = state.pop_from_stack() else {
+                    return;
+                };
+                state.set_memory_at_reg(reg, value as u8);
             }
         }
     }
@@ -438,12 +607,13 @@ impl ProcessInstruction for InstructionCall {
         }
 
         let address = match *self {
-            Self::byte { .. } => state.advance_pc(),
+            Self::byte { .. } => state.advance_pc_word(),
             Self::reg(reg) | Self::ifZ_reg(reg) => state.get_reg(reg),
         };
 
-        state.push_to_stack(state.get_reg_pc());
-        state.set_reg_pc(address);
+        if state.push_to_stack(state.get_reg_pc()) {
+            state.set_reg_pc(address);
+        }
     }
 }
 
@@ -459,8 +629,9 @@ impl ProcessInstruction for InstructionRet {
             return;
         }
 
-        let address = state.pop_from_stack();
-        state.set_reg_pc(address);
+        if let Some(address) = state.pop_from_stack() {
+            state.set_reg_pc(address);
+        }
     }
 }
 
@@ -475,20 +646,20 @@ pub enum InstructionLeftShift {
 
 impl ProcessInstruction for InstructionLeftShift {
     fn process(&self, state: &mut CellPair) {
-        let value = match *self {
-            Self::reg(reg) => state.get_reg(reg),
-            Self::atReg(reg) => state.get_memory_at_reg(reg),
+        let (value, mask) = match *self {
+            Self::reg(reg) => (state.get_reg(reg), state.word_mask()),
+            Self::atReg(reg) => (state.get_memory_at_reg(reg) as Word, u8::MAX as Word),
         };
 
-        let result = value.wrapping_shl(1);
+        let result = value.wrapping_shl(1) & mask;
 
         state.set_flag_z(result == 0);
         state.set_flag_n(false);
-        state.set_flag_c(value & 0b1000_0000 != 0);
+        state.set_flag_c(value & (mask ^ (mask >> 1)) != 0);
 
         match *self {
             Self::reg(reg) => state.set_reg(reg, result),
-            Self::atReg(reg) => state.set_memory_at_reg(reg, result),
+            Self::atReg(reg) => state.set_memory_at_reg(reg, result as u8),
         }
     }
 }
@@ -506,7 +677,7 @@ impl ProcessInstruction for InstructionRightShift {
     fn process(&self, state: &mut CellPair) {
         let value = match *self {
             Self::reg(reg) => state.get_reg(reg),
-            Self::atReg(reg) => state.get_memory_at_reg(reg),
+            Self::atReg(reg) => state.get_memory_at_reg(reg) as Word,
         };
 
         let result = value.wrapping_shr(1);
@@ -517,7 +688,7 @@ impl ProcessInstruction for InstructionRightShift {
 
         match *self {
             Self::reg(reg) => state.set_reg(reg, result),
-            Self::atReg(reg) => state.set_memory_at_reg(reg, result),
+            Self::atReg(reg) => state.set_memory_at_reg(reg, result as u8),
         }
     }
 }
@@ -536,8 +707,8 @@ impl ProcessInstruction for InstructionCompare {
     fn process(&self, state: &mut CellPair) {
         let (a, b) = match *self {
             Self::a_reg(reg) => (state.get_reg_acc(), state.get_reg(reg)),
-            Self::a_byte => (state.get_reg_acc(), state.advance_pc()),
-            Self::atA_byte => (state.get_memory_at_acc(), state.advance_pc()),
+            Self::a_byte => (state.get_reg_acc(), state.advance_pc_word()),
+            Self::atA_byte => (state.get_memory_at_acc() as Word, state.advance_pc_word()),
         };
 
         state.set_flag_z(a == b);
@@ -557,8 +728,107 @@ impl ProcessInstruction for InstructionReplicate {
         // std::mem::swap(state.main, state.neighbor);
 
         // for _ in 0..8 {
-        //     let mutate_address = ::rand::random::<u8>() as usize % CellState::MEMORY_SIZE;
+        //     let mutate_address = ::rand::random::<usize>() % state.config.memory_size;
         //     state.main.memory[mutate_address] = ::rand::random();
         // }
     }
 }
+
+/// Protect byte at [a] from writes of other cells. Sets z if the byte is now owned by this cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionProtect;
+
+impl ProcessInstruction for InstructionProtect {
+    fn process(&self, state: &mut CellPair) {
+        if state.config.write_protection.is_none() {
+            return;
+        }
+
+        let owner = state.context.main_index;
+        let tag = state.get_tag_mut(state.get_reg_acc());
+        let is_owned = tag.is_writable_by(owner);
+        if is_owned {
+            tag.owner = owner;
+            tag.is_protected = true;
+        }
+
+        state.set_flag_z(is_owned);
+    }
+}
+
+/// Remove protection of byte at [a]. Sets z if the byte is now unprotected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionUnprotect;
+
+impl ProcessInstruction for InstructionUnprotect {
+    fn process(&self, state: &mut CellPair) {
+        if state.config.write_protection.is_none() {
+            return;
+        }
+
+        let owner = state.context.main_index;
+        let tag = state.get_tag_mut(state.get_reg_acc());
+        let is_owned = tag.is_writable_by(owner);
+        if is_owned {
+            tag.is_protected = false;
+        }
+
+        state.set_flag_z(is_owned);
+    }
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionRandom {
+    /// Load random byte from the pair's generator to given register.
+    reg(Register),
+}
+
+impl ProcessInstruction for InstructionRandom {
+    fn process(&self, state: &mut CellPair) {
+        match *self {
+            Self::reg(reg) => {
+                let value = state.rng.gen::<u8>();
+                state.set_reg(reg, value as Word);
+            }
+        }
+    }
+}
+
+/// Load information about the pair's environment to accumulator.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionSense {
+    /// Direction from the main cell to the neighbor, see [`Direction`].
+    direction,
+    /// X coordinate of the cell modulo 256.
+    x,
+    /// Y coordinate of the cell modulo 256.
+    y,
+    /// Low byte of the world tick counter.
+    tick,
+    /// 1 if the instruction was fetched from the main cell memory, 0 if the program counter
+    /// ran into the neighbor, so code copied into a neighbor can tell it is not running in
+    /// its own cell.
+    role,
+}
+
+impl ProcessInstruction for InstructionSense {
+    fn process(&self, state: &mut CellPair) {
+        let context = state.context;
+
+        let value = match *self {
+            Self::direction => context.direction as u8,
+            Self::x => context.main_position.x as u8,
+            Self::y => context.main_position.y as u8,
+            Self::tick => context.tick as u8,
+            Self::role => {
+                // the program counter is already past the sense instruction
+                let address = state.get_reg_pc().wrapping_sub(1) & state.word_mask();
+                state.is_main_address(address) as u8
+            }
+        };
+
+        state.set_reg_acc(value as Word);
+    }
+}
@@ -0,0 +1,15 @@
+/// Shadow metadata of a single memory byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MemoryTag {
+    /// Index of the cell that protected the byte.
+    pub owner: u32,
+    /// Only the owner can write into a protected byte.
+    pub is_protected: bool,
+}
+
+impl MemoryTag {
+    #[inline(always)]
+    pub fn is_writable_by(&self, cell_index: u32) -> bool {
+        !self.is_protected || self.owner == cell_index
+    }
+}
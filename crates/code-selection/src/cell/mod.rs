@@ -1,7 +1,23 @@
+mod assembler;
 mod cell_pair;
 mod cell_state;
+mod control_flow;
+mod disassembler;
 mod instruction;
+mod memory_tag;
+mod pair_context;
+mod provenance;
+mod step_record;
+mod vm_config;
 
+pub use assembler::*;
 pub use cell_pair::*;
 pub use cell_state::*;
+pub use control_flow::*;
+pub use disassembler::*;
 pub use instruction::*;
+pub use memory_tag::*;
+pub use pair_context::*;
+pub use provenance::*;
+pub use step_record::*;
+pub use vm_config::*;
@@ -0,0 +1,16 @@
+use crate::*;
+
+/// Where and when the pair is executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PairContext {
+    /// Index of the main cell in the world, used as owner id of protected bytes.
+    pub main_index: u32,
+    /// Index of the neighbor cell in the world.
+    pub neighbor_index: u32,
+    /// Position of the main cell in the world.
+    pub main_position: RelativePosition,
+    /// Direction from the main cell to the neighbor.
+    pub direction: Direction,
+    /// World tick at which the pair is executed.
+    pub tick: u64,
+}
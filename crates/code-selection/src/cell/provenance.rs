@@ -0,0 +1,246 @@
+use crate::*;
+
+/// Main cell which computed a value and the tick it happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    pub cell: u32,
+    pub tick: u64,
+    /// Tracer token of the memory byte the value was copied from, see
+    /// [`World::track_provenance`].
+    pub token: u32,
+}
+
+impl Provenance {
+    /// Token of computed values which were never a memory byte when tracking started.
+    pub const NO_TOKEN: u32 = u32::MAX;
+
+    /// Value computed by the cell at the tick.
+    pub fn computed(cell: u32, tick: u64) -> Self {
+        Self {
+            cell,
+            tick,
+            token: Self::NO_TOKEN,
+        }
+    }
+}
+
+/// Shadow of the memory and registers of a cell with the provenance of every value.
+///
+/// Values moved by loads, pushes and pops keep their provenance, any other write originates
+/// from the main cell of the pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellProvenance {
+    pub memory: Vec<Provenance>,
+    pub registers: [Provenance; CellState::REGISTERS_COUNT],
+    /// Shadow of [`CellState::stack`], allocated along with it.
+    pub stack: Vec<Provenance>,
+}
+
+impl CellProvenance {
+    /// Every value originates from the same cell and tick.
+    pub fn new(origin: Provenance, memory_size: usize) -> Self {
+        Self {
+            memory: vec![origin; memory_size],
+            registers: [origin; CellState::REGISTERS_COUNT],
+            stack: Vec::new(),
+        }
+    }
+}
+
+/// How far the bytes originating from a single cell spread over the world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProvenanceSpread {
+    pub origin: u32,
+    pub bytes: usize,
+    /// Number of cells holding any of the bytes.
+    pub cells: usize,
+    /// Largest distance in steps between adjacent cells from the origin to a holding cell.
+    pub max_distance: usize,
+    pub mean_distance: f64,
+    /// Largest distance per tick travelled by a byte since it originated.
+    pub max_speed: f64,
+}
+
+impl World {
+    /// Start recording provenance, every value originates from its cell at the current tick.
+    ///
+    /// Every memory byte gets a unique tracer token, `cell_index * memory_size + address`.
+    /// Fails if the world has more bytes than there are tokens.
+    pub fn track_provenance(&mut self) -> Result<(), String> {
+        let bytes = self.cells.len() * self.vm_config.memory_size;
+        if bytes > Provenance::NO_TOKEN as usize {
+            return Err(format!(
+                "{bytes} memory bytes do not fit into {} provenance tokens",
+                Provenance::NO_TOKEN
+            ));
+        }
+
+        let tick = self.tick_count;
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            let origin = Provenance::computed(index as u32, tick);
+            let mut shadow = CellProvenance::new(origin, cell.memory.len());
+            for (address, byte) in shadow.memory.iter_mut().enumerate() {
+                byte.token = (index * cell.memory.len() + address) as u32;
+            }
+            cell.provenance = Some(Box::new(shadow));
+        }
+
+        Ok(())
+    }
+
+    pub fn tracks_provenance(&self) -> bool {
+        self.cells
+            .first()
+            .is_some_and(|cell| cell.provenance.is_some())
+    }
+
+    /// Spread of every origin with bytes left in the world, the most widespread first.
+    pub fn provenance_spread(&self) -> Vec<ProvenanceSpread> {
+        let mut spreads = nohash_hasher::IntMap::<u32, (ProvenanceSpread, usize)>::default();
+
+        for (index, cell) in self.cells.iter().enumerate() {
+            let Some(provenance) = &cell.provenance else {
+                continue;
+            };
+            let position = self.size.index_to_coords(index);
+
+            for byte in &provenance.memory {
+                let origin = self.size.index_to_coords(byte.cell as usize);
+                let dx = position.x.abs_diff(origin.x) as usize;
+                let dy = position.y.abs_diff(origin.y) as usize;
+                let distance = dx.min(self.size.width - dx) + dy.min(self.size.height - dy);
+                let age = self.tick_count.saturating_sub(byte.tick).max(1);
+
+                let (spread, last_cell) = spreads.entry(byte.cell).or_insert((
+                    ProvenanceSpread {
+                        origin: byte.cell,
+                        bytes: 0,
+                        cells: 0,
+                        max_distance: 0,
+                        mean_distance: 0.0,
+                        max_speed: 0.0,
+                    },
+                    usize::MAX,
+                ));
+                if *last_cell != index {
+                    *last_cell = index;
+                    spread.cells += 1;
+                }
+                spread.bytes += 1;
+                spread.max_distance = spread.max_distance.max(distance);
+                spread.mean_distance += distance as f64;
+                spread.max_speed = spread.max_speed.max(distance as f64 / age as f64);
+            }
+        }
+
+        let mut result = spreads
+            .into_values()
+            .map(|(mut spread, _)| {
+                spread.mean_distance /= spread.bytes as f64;
+                spread
+            })
+            .collect::<Vec<_>>();
+        result.sort_unstable_by_key(|spread| (std::cmp::Reverse(spread.bytes), spread.origin));
+
+        result
+    }
+}
+
+#[test]
+fn test_provenance() {
+    let config = VmConfig::default();
+    let source = "
+        ld a, 0x90
+        ld b, [a]       ; a byte of the neighbor
+        ld a, 0x40
+        ld [a], b       ; copied into main
+        add a, b        ; computed by main
+        ld c, a
+        push c          ; the stack pointer starts at zero and wraps into the neighbor
+    ";
+    let mut world = World::with_seed(AreaSize::new(2, 2), config, 0);
+    world.cells[0] = Stamp::program_cell(assemble(source, &config).unwrap(), &config).unwrap();
+    world.cells[1] = Stamp::program_cell(Vec::new(), &config).unwrap();
+    world.track_provenance().unwrap();
+
+    let mut main = world.cells[0].clone();
+    let mut neighbor = world.cells[1].clone();
+    let mut pair = CellPair::new(&mut main, &mut neighbor, config).with_context(PairContext {
+        main_index: 0,
+        neighbor_index: 1,
+        tick: 5,
+        ..Default::default()
+    });
+    for _ in 0..7 {
+        pair.step();
+    }
+
+    let neighbor_byte = Provenance {
+        cell: 1,
+        tick: 0,
+        token: config.memory_size as u32 + 0x10,
+    };
+    let computed = Provenance::computed(0, 5);
+    let provenance = main.provenance.as_ref().unwrap();
+    assert_eq!(provenance.memory[0x40], neighbor_byte);
+    assert_eq!(provenance.registers[CellState::REGISTER_B], neighbor_byte);
+    assert_eq!(
+        provenance.registers[CellState::REGISTER_ACCUMULATOR],
+        computed
+    );
+    assert_eq!(provenance.registers[CellState::REGISTER_C], computed);
+    let provenance = neighbor.provenance.as_ref().unwrap();
+    assert_eq!(provenance.memory[config.memory_size - 1], computed);
+
+    world.cells[0] = main;
+    world.cells[1] = neighbor;
+    world.tick_count = 5;
+    let spread = world.provenance_spread();
+    let from_neighbor = spread.iter().find(|spread| spread.origin == 1).unwrap();
+    assert_eq!(from_neighbor.cells, 2);
+    assert_eq!(from_neighbor.max_distance, 1);
+    assert_eq!(from_neighbor.max_speed, 1.0 / 5.0);
+}
+
+#[test]
+fn test_provenance_separate_stack() {
+    let config = VmConfig {
+        stack_mode: StackMode::Separate { size: 4 },
+        ..Default::default()
+    };
+    let source = "
+        ld a, 0x90
+        ld b, [a]       ; a byte of the neighbor
+        push b
+        pop c           ; moved through the stack outside of the memory
+        ld a, 0x40
+        ld [a], c
+    ";
+    let mut world = World::with_seed(AreaSize::new(2, 2), config, 0);
+    world.cells[0] = Stamp::program_cell(assemble(source, &config).unwrap(), &config).unwrap();
+    world.cells[1] = Stamp::program_cell(Vec::new(), &config).unwrap();
+    world.track_provenance().unwrap();
+
+    let (main, neighbor) = world.cells.split_at_mut(1);
+    let mut pair =
+        CellPair::new(&mut main[0], &mut neighbor[0], config).with_context(PairContext {
+            main_index: 0,
+            neighbor_index: 1,
+            tick: 5,
+            ..Default::default()
+        });
+    for _ in 0..6 {
+        pair.step();
+    }
+
+    let neighbor_byte = Provenance {
+        cell: 1,
+        tick: 0,
+        token: config.memory_size as u32 + 0x10,
+    };
+    let provenance = world.cells[0].provenance.as_ref().unwrap();
+    assert_eq!(provenance.stack[3], neighbor_byte);
+    assert_eq!(provenance.stack[0], Provenance::computed(0, 5));
+    assert_eq!(provenance.registers[CellState::REGISTER_C], neighbor_byte);
+    assert_eq!(provenance.memory[0x40], neighbor_byte);
+}
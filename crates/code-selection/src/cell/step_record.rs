@@ -0,0 +1,42 @@
+use crate::*;
+
+/// Everything that happened during execution of a single instruction, see [`CellPair::step`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepRecord {
+    /// Program counter before the instruction was read.
+    pub pc: Word,
+    pub opcode: u8,
+    pub instruction: Instruction,
+    /// Registers of the main cell which value changed.
+    pub register_changes: Vec<RegisterChange>,
+    /// Every write into the pair memory in order of execution.
+    pub memory_writes: Vec<MemoryWrite>,
+    /// Every write into the stack of the main cell outside of the pair memory, see
+    /// [`StackMode::Separate`] and [`StackMode::Trap`].
+    pub stack_writes: Vec<StackWrite>,
+    /// The pair ran out of cycles after this instruction.
+    pub is_turn_over: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisterChange {
+    pub register: Register,
+    pub old: Word,
+    pub new: Word,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemoryWrite {
+    /// Address in the pair memory, wrapped around the address space.
+    pub address: usize,
+    pub old: u8,
+    pub new: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StackWrite {
+    /// Index of the slot in the stack of the main cell.
+    pub index: usize,
+    pub old: Word,
+    pub new: Word,
+}
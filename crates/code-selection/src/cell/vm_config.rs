@@ -0,0 +1,341 @@
+use crate::*;
+use std::str::FromStr;
+
+/// Settings shared by every [`crate::CellPair`] of the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VmConfig {
+    /// Memory size of a single cell. The pair addresses twice as much.
+    pub memory_size: usize,
+    /// Width of registers, immediate values and stack slots.
+    pub address_mode: AddressMode,
+    /// Which opcode map is used to decode instructions.
+    pub opcode_revision: OpcodeRevision,
+    /// Enforce per-byte write protection. `None` disables protection entirely and turns
+    /// protect/unprotect instructions into nops.
+    pub write_protection: Option<ProtectionPenalty>,
+    /// Where the stack lives and what happens when it runs out.
+    pub stack_mode: StackMode,
+}
+
+impl Default for VmConfig {
+    fn default() -> Self {
+        Self {
+            memory_size: CellState::DEFAULT_MEMORY_SIZE,
+            address_mode: AddressMode::default(),
+            opcode_revision: OpcodeRevision::default(),
+            write_protection: None,
+            stack_mode: StackMode::default(),
+        }
+    }
+}
+
+impl VmConfig {
+    /// Number of addressable bytes of the pair, addresses wrap around it.
+    #[inline(always)]
+    pub const fn address_space(&self) -> usize {
+        self.memory_size * 2
+    }
+
+    /// Check that the memory and the stack of the pair can be addressed by the registers.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.memory_size == 0
+            || self.address_space() > self.address_mode.word_mask() as usize + 1
+        {
+            return Err(format!(
+                "memory size {} does not fit into {:?} address mode",
+                self.memory_size, self.address_mode
+            ));
+        }
+
+        if let StackMode::Separate { size } | StackMode::Trap { size } = self.stack_mode {
+            let max_size = self.address_mode.word_mask() as usize;
+            if size == 0 || size > max_size {
+                return Err(format!(
+                    "stack size {size} must be between 1 and {max_size} in {:?} address mode",
+                    self.address_mode
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// [`fnv1a_hash`] of the settings, identifies runs with the same config in exported
+    /// metrics.
+    ///
+    /// Every setting is encoded with fixed codes, so renaming or reordering types does not
+    /// change the hash.
+    pub fn stable_hash(&self) -> u64 {
+        let address_mode = match self.address_mode {
+            AddressMode::Bits8 => 0,
+            AddressMode::Bits16 => 1,
+        };
+        let opcode_revision = match self.opcode_revision {
+            OpcodeRevision::V0 => 0,
+            OpcodeRevision::V1 => 1,
+            OpcodeRevision::V2 => 2,
+            OpcodeRevision::V3 => 3,
+        };
+        let (write_protection, penalty_cycles) = match self.write_protection {
+            None => (0, 0),
+            Some(ProtectionPenalty::DropWrite) => (1, 0),
+            Some(ProtectionPenalty::Cycles(cycles)) => (2, cycles as u64),
+            Some(ProtectionPenalty::EndTurn) => (3, 0),
+        };
+        let (stack_mode, stack_size) = match self.stack_mode {
+            StackMode::Wrap => (0, 0),
+            StackMode::Separate { size } => (1, size as u64),
+            StackMode::Trap { size } => (2, size as u64),
+        };
+
+        let fields = [
+            self.memory_size as u64,
+            address_mode,
+            opcode_revision,
+            write_protection,
+            penalty_cycles,
+            stack_mode,
+            stack_size,
+        ];
+
+        fnv1a_hash(fields.iter().flat_map(|field| field.to_le_bytes()))
+    }
+
+    /// Returns the size of the cell on the world canvas.
+    pub fn cell_canvas_size(&self) -> AreaSize {
+        let register_bytes = CellState::REGISTERS_COUNT * self.address_mode.word_bytes() as usize;
+        let pixels = register_bytes.div_ceil(3) + self.memory_size.div_ceil(3);
+
+        let mut side = pixels.isqrt();
+        if side * side < pixels {
+            side += 1;
+        }
+
+        AreaSize::splat(side)
+    }
+}
+
+/// Width of registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AddressMode {
+    /// 8-bit registers, up to 256 bytes of pair memory.
+    #[default]
+    Bits8,
+    /// 16-bit registers, up to 64KiB of pair memory. Immediate values and stack slots are
+    /// 2 bytes long (little-endian), data bytes accessed through `[reg]` are still 1 byte long.
+    Bits16,
+}
+
+impl AddressMode {
+    #[inline(always)]
+    pub const fn word_mask(self) -> Word {
+        match self {
+            Self::Bits8 => 0xFF,
+            Self::Bits16 => 0xFFFF,
+        }
+    }
+
+    /// Most significant bit of the register.
+    #[inline(always)]
+    pub const fn sign_mask(self) -> Word {
+        match self {
+            Self::Bits8 => 0x80,
+            Self::Bits16 => 0x8000,
+        }
+    }
+
+    #[inline(always)]
+    pub const fn word_bytes(self) -> Word {
+        match self {
+            Self::Bits8 => 1,
+            Self::Bits16 => 2,
+        }
+    }
+}
+
+/// Parses `8` or `16`.
+impl FromStr for AddressMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "8" => Ok(Self::Bits8),
+            "16" => Ok(Self::Bits16),
+            _ => Err(format!("unknown address mode `{value}`")),
+        }
+    }
+}
+
+/// Revision of the opcode map. Every revision is a superset of the previous one, new
+/// instructions take over opcodes of the `call reg` and `call z reg` blocks which duplicate
+/// `jmp reg` and `jmp z reg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum OpcodeRevision {
+    /// Original instruction set.
+    #[default]
+    V0,
+    /// Adds `prot [a]` and `unprot [a]`.
+    V1,
+    /// Adds `rnd reg`.
+    V2,
+    /// Adds `sense` instructions.
+    V3,
+}
+
+/// Parses revision number, `0` to `3`.
+impl FromStr for OpcodeRevision {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "0" => Ok(Self::V0),
+            "1" => Ok(Self::V1),
+            "2" => Ok(Self::V2),
+            "3" => Ok(Self::V3),
+            _ => Err(format!("unknown opcode revision `{value}`")),
+        }
+    }
+}
+
+/// Where the stack of the cell lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StackMode {
+    /// Stack lives in the pair memory, sp wraps around the whole address space and may
+    /// overwrite code of both cells.
+    #[default]
+    Wrap,
+    /// Stack lives in a per-cell array of given size outside of the addressable memory,
+    /// sp wraps around the array.
+    Separate { size: usize },
+    /// Same as [`StackMode::Separate`], but overflow and underflow trap: the pair's turn ends
+    /// and the trap is counted in [`TickStats::stack_traps`].
+    Trap { size: usize },
+}
+
+/// Parses `wrap`, `separate:<size>` or `trap:<size>`.
+impl FromStr for StackMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let size = |size: &str| {
+            size.parse()
+                .map_err(|err| format!("invalid stack size `{size}`: {err}"))
+        };
+
+        match value.split_once(':') {
+            None if value == "wrap" => Ok(Self::Wrap),
+            Some(("separate", size_value)) => Ok(Self::Separate {
+                size: size(size_value)?,
+            }),
+            Some(("trap", size_value)) => Ok(Self::Trap {
+                size: size(size_value)?,
+            }),
+            _ => Err(format!("unknown stack mode `{value}`")),
+        }
+    }
+}
+
+/// What happens when a cell tries to write into a byte protected by another cell.
+/// The write itself is always dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProtectionPenalty {
+    /// Only drop the write.
+    #[default]
+    DropWrite,
+    /// Drop the write and take given amount of cycles from the pair's budget.
+    Cycles(usize),
+    /// Drop the write and end the pair's turn.
+    EndTurn,
+}
+
+/// Parses `drop`, `cycles:<n>` or `end`.
+impl FromStr for ProtectionPenalty {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            None if value == "drop" => Ok(Self::DropWrite),
+            None if value == "end" => Ok(Self::EndTurn),
+            Some(("cycles", cycles)) => cycles
+                .parse()
+                .map(Self::Cycles)
+                .map_err(|err| format!("invalid cycles `{cycles}`: {err}")),
+            _ => Err(format!("unknown protection penalty `{value}`")),
+        }
+    }
+}
+
+#[test]
+fn test_validate() {
+    let config = |memory_size, address_mode, stack_mode| VmConfig {
+        memory_size,
+        address_mode,
+        stack_mode,
+        ..Default::default()
+    };
+
+    assert!(
+        config(128, AddressMode::Bits8, StackMode::Trap { size: 16 })
+            .validate()
+            .is_ok()
+    );
+    assert!(config(256, AddressMode::Bits8, StackMode::Wrap)
+        .validate()
+        .is_err());
+    assert!(config(128, AddressMode::Bits8, StackMode::Trap { size: 0 })
+        .validate()
+        .is_err());
+    assert!(
+        config(128, AddressMode::Bits8, StackMode::Separate { size: 0 })
+            .validate()
+            .is_err()
+    );
+    assert!(
+        config(128, AddressMode::Bits16, StackMode::Separate { size: 1000 })
+            .validate()
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_stable_hash() {
+    // exported runs are identified by the hash, it must not change across versions
+    let config = VmConfig::default();
+    assert_eq!(config.stable_hash(), 0xcee3_fd9b_0e8a_5605);
+
+    let other = [
+        VmConfig {
+            memory_size: 64,
+            ..config
+        },
+        VmConfig {
+            address_mode: AddressMode::Bits16,
+            ..config
+        },
+        VmConfig {
+            opcode_revision: OpcodeRevision::V3,
+            ..config
+        },
+        VmConfig {
+            write_protection: Some(ProtectionPenalty::DropWrite),
+            ..config
+        },
+        VmConfig {
+            write_protection: Some(ProtectionPenalty::Cycles(1)),
+            ..config
+        },
+        VmConfig {
+            stack_mode: StackMode::Separate { size: 16 },
+            ..config
+        },
+        VmConfig {
+            stack_mode: StackMode::Trap { size: 16 },
+            ..config
+        },
+    ];
+    let mut hashes = other.map(|config| config.stable_hash()).to_vec();
+    hashes.push(config.stable_hash());
+    hashes.sort_unstable();
+    hashes.dedup();
+    assert_eq!(hashes.len(), other.len() + 1);
+}
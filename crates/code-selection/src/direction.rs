@@ -1,6 +1,7 @@
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash, Default)]
 pub enum Direction {
+    #[default]
     Up,
     Down,
     Left,
@@ -1,15 +1,31 @@
 mod app_state;
 mod area_size;
+mod breakpoint;
 mod cell;
 mod direction;
+mod lineage;
+mod metrics;
 mod position;
+mod replicator;
 mod slice_multi_borrow;
+mod stable_hash;
+mod stamp;
+mod tick_stats;
+mod tracer;
 mod world;
 
 pub use app_state::*;
 pub use area_size::*;
+pub use breakpoint::*;
 pub use cell::*;
 pub use direction::*;
+pub use lineage::*;
+pub use metrics::*;
 pub use position::*;
+pub use replicator::*;
 pub use slice_multi_borrow::*;
+pub use stable_hash::*;
+pub use stamp::*;
+pub use tick_stats::*;
+pub use tracer::*;
 pub use world::*;
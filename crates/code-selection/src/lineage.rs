@@ -0,0 +1,302 @@
+use crate::*;
+use nohash_hasher::IntMap;
+use std::fmt::Write;
+
+/// Genome identity of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lineage {
+    /// [`CellState::memory_hash`] of the memory the cell got when it was copied.
+    pub genome: u64,
+    /// Genome of the cell which copied itself into this one, `None` for the founders.
+    pub parent: Option<u64>,
+}
+
+/// Phylogeny of genomes built from copies between paired cells.
+#[derive(Debug, Clone)]
+pub struct LineageTracker {
+    /// Share of the neighbor memory main must overwrite with its own bytes for the neighbor
+    /// to count as a copy of main.
+    pub copy_threshold: f64,
+    /// Genomes produced by copies, founders are only referenced as parents.
+    pub genomes: IntMap<u64, GenomeNode>,
+    /// Copies in progress by the world index of the neighbor being written.
+    pending: IntMap<u32, PendingCopy>,
+}
+
+/// Bytes a genome copied into a neighbor over the turns they were paired.
+///
+/// A turn is too short to copy most of a memory, so the bytes add up until another genome
+/// writes into the neighbor.
+#[derive(Debug, Clone)]
+struct PendingCopy {
+    parent: u64,
+    copied: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenomeNode {
+    pub parent: u64,
+    /// Tick of the first copy producing the genome.
+    pub tick: u64,
+    /// Number of copies producing the genome.
+    pub copies: u64,
+}
+
+impl Default for LineageTracker {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_COPY_THRESHOLD)
+    }
+}
+
+impl LineageTracker {
+    pub const DEFAULT_COPY_THRESHOLD: f64 = 0.5;
+
+    pub fn new(copy_threshold: f64) -> Self {
+        Self {
+            copy_threshold,
+            genomes: IntMap::default(),
+            pending: IntMap::default(),
+        }
+    }
+
+    /// Update the neighbor's lineage if main has overwritten enough of it with its own bytes.
+    ///
+    /// `written` are the neighbor memory indices main wrote during the interaction, a write
+    /// counts if the byte equals the main byte at the same index.
+    pub fn observe(
+        &mut self,
+        main: &mut CellState,
+        neighbor: &mut CellState,
+        neighbor_index: u32,
+        written: &[usize],
+        tick: u64,
+    ) {
+        let parent = match main.lineage {
+            Some(lineage) => lineage,
+            None => *main.lineage.insert(Lineage {
+                genome: main.memory_hash(),
+                parent: None,
+            }),
+        };
+
+        let pending = self
+            .pending
+            .entry(neighbor_index)
+            .or_insert_with(|| PendingCopy {
+                parent: parent.genome,
+                copied: vec![false; neighbor.memory.len()],
+            });
+        if pending.parent != parent.genome {
+            pending.parent = parent.genome;
+            pending.copied.fill(false);
+        }
+        for &index in written {
+            pending.copied[index] = true;
+        }
+
+        // bytes overwritten later by something else than main do not count
+        let copied = pending
+            .copied
+            .iter()
+            .zip(main.memory.iter().zip(&neighbor.memory))
+            .filter(|&(&copied, (main, neighbor))| copied && main == neighbor)
+            .count();
+        if (copied as f64) < self.copy_threshold * neighbor.memory.len() as f64 {
+            return;
+        }
+        self.pending.remove(&neighbor_index);
+        let genome = neighbor.memory_hash();
+        if genome == parent.genome {
+            neighbor.lineage = Some(parent);
+            return;
+        }
+
+        neighbor.lineage = Some(Lineage {
+            genome,
+            parent: Some(parent.genome),
+        });
+
+        if let Some(node) = self.genomes.get_mut(&genome) {
+            node.copies += 1;
+        } else if !self.is_ancestor(genome, parent.genome) {
+            self.genomes.insert(
+                genome,
+                GenomeNode {
+                    parent: parent.genome,
+                    tick,
+                    copies: 1,
+                },
+            );
+        }
+    }
+
+    /// Whether `genome` is `descendant` or one of its recorded ancestors.
+    fn is_ancestor(&self, genome: u64, mut descendant: u64) -> bool {
+        loop {
+            if descendant == genome {
+                return true;
+            }
+            match self.genomes.get(&descendant) {
+                Some(node) => descendant = node.parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Phylogeny in Newick format, one tree per founder which has descendants.
+    ///
+    /// Genomes are labeled by their hex hash and branch lengths are in ticks.
+    pub fn to_newick(&self) -> String {
+        let mut children = IntMap::<u64, Vec<u64>>::default();
+        for (&genome, node) in &self.genomes {
+            children.entry(node.parent).or_default().push(genome);
+        }
+        for genomes in children.values_mut() {
+            genomes.sort_unstable_by_key(|genome| (self.genomes[genome].tick, *genome));
+        }
+
+        let mut founders = children
+            .keys()
+            .copied()
+            .filter(|genome| !self.genomes.contains_key(genome))
+            .collect::<Vec<_>>();
+        founders.sort_unstable();
+
+        enum Step {
+            Enter(u64),
+            Separator,
+            Exit(u64),
+        }
+
+        let mut result = String::new();
+        for founder in founders {
+            // iterative to survive long chains of copies
+            let mut stack = vec![Step::Enter(founder)];
+            while let Some(step) = stack.pop() {
+                let genome = match step {
+                    Step::Enter(genome) => match children.get(&genome) {
+                        Some(genomes) => {
+                            result.push('(');
+                            stack.push(Step::Exit(genome));
+                            for (i, &child) in genomes.iter().rev().enumerate() {
+                                if i > 0 {
+                                    stack.push(Step::Separator);
+                                }
+                                stack.push(Step::Enter(child));
+                            }
+                            continue;
+                        }
+                        None => genome,
+                    },
+                    Step::Separator => {
+                        result.push(',');
+                        continue;
+                    }
+                    Step::Exit(genome) => {
+                        result.push(')');
+                        genome
+                    }
+                };
+
+                write!(result, "{genome:016x}").unwrap();
+                if let Some(node) = self.genomes.get(&genome) {
+                    let parent_tick = self
+                        .genomes
+                        .get(&node.parent)
+                        .map_or(0, |parent| parent.tick);
+                    write!(result, ":{}", node.tick.saturating_sub(parent_tick)).unwrap();
+                }
+            }
+            result.push_str(";\n");
+        }
+
+        result
+    }
+}
+
+impl World {
+    /// Start tracking lineages, every current cell becomes a founder.
+    pub fn track_lineage(&mut self, tracker: LineageTracker) {
+        for cell in &mut self.cells {
+            cell.lineage = Some(Lineage {
+                genome: cell.memory_hash(),
+                parent: None,
+            });
+        }
+        self.lineage = Some(tracker);
+    }
+}
+
+#[test]
+fn test_lineage_tracker() {
+    let config = VmConfig::default();
+    let mut tracker = LineageTracker::default();
+
+    let program = |bytes: &[u8]| Stamp::program_cell(bytes.to_vec(), &config).unwrap();
+    let mut founder = program(&[1; 100]);
+    let mut child = program(&[]);
+    let mut grandchild = program(&[]);
+
+    // equal bytes main did not write do not make a copy
+    child.memory[..80].fill(1);
+    tracker.observe(&mut founder, &mut child, 1, &[0], 1);
+    assert_eq!(child.lineage, None);
+    let founder_genome = founder.memory_hash();
+    assert_eq!(founder.lineage.unwrap().genome, founder_genome);
+
+    // writes of the same genome add up over the turns
+    tracker.observe(&mut founder, &mut child, 1, &Vec::from_iter(1..40), 2);
+    assert_eq!(child.lineage, None);
+    tracker.observe(&mut founder, &mut child, 1, &Vec::from_iter(40..80), 3);
+    assert_eq!(
+        child.lineage,
+        Some(Lineage {
+            genome: child.memory_hash(),
+            parent: Some(founder_genome),
+        })
+    );
+
+    // bytes differing from main do not count and another genome restarts the copy
+    grandchild.memory[..70].fill(1);
+    tracker.observe(&mut child, &mut grandchild, 2, &Vec::from_iter(60..128), 4);
+    tracker.observe(&mut founder, &mut grandchild, 2, &Vec::from_iter(0..60), 5);
+    tracker.observe(&mut child, &mut grandchild, 2, &Vec::from_iter(0..60), 6);
+    assert_eq!(grandchild.lineage, None);
+    tracker.observe(&mut child, &mut grandchild, 2, &Vec::from_iter(60..70), 7);
+    assert_eq!(
+        grandchild.lineage.unwrap().parent,
+        Some(child.memory_hash())
+    );
+
+    // a copy back into the founder genome does not make a cycle
+    let mut copy = program(&[1; 100]);
+    tracker.observe(&mut child, &mut copy, 3, &Vec::from_iter(0..128), 8);
+    assert_eq!(tracker.genomes.len(), 2);
+
+    assert_eq!(
+        tracker.to_newick(),
+        format!(
+            "(({:016x}:4){:016x}:3){founder_genome:016x};\n",
+            grandchild.memory_hash(),
+            child.memory_hash()
+        )
+    );
+}
+
+#[test]
+fn test_newick_parent_recorded_after_child() {
+    let mut tracker = LineageTracker::default();
+    let node = |parent, tick| GenomeNode {
+        parent,
+        tick,
+        copies: 1,
+    };
+    tracker.genomes.insert(2, node(1, 5));
+    // the parent genome was a founder when copied and appeared as a copy later
+    tracker.genomes.insert(1, node(0, 9));
+
+    assert_eq!(
+        tracker.to_newick(),
+        "((0000000000000002:0)0000000000000001:9)0000000000000000;\n"
+    );
+}
@@ -15,7 +15,83 @@ fn window_conf() -> Conf {
 
 #[macroquad::main(window_conf)]
 async fn main() {
-    let mut state = AppState::new(AreaSize::splat(128));
+    // `--memory-size <n>`, `--bits16`, `--revision <0..3>`, `--stack <mode>` and
+    // `--protection <mode>` configure the VM the same way as in headless
+    let (vm_config, args) = match parse_vm_config(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    };
+    let mut state = AppState::new(AreaSize::splat(128), vm_config);
+
+    // `--replicator <name>[=<fraction>]` seeds the world with a built-in replicator,
+    // `--metrics-interval <ticks>` samples metrics every given number of ticks,
+    // `--spatial` samples domains of similar genomes along with the metrics,
+    // `--metrics-out <path>` writes every sample to a .csv or .jsonl file,
+    // `--pause-on-transition` pauses when replicators take over,
+    // metrics are sampled only if any of the options above is set, every 256 ticks by default,
+    // `--lineage` tracks which cells copied which,
+    // `--provenance` tracks the origin and tracer token of every byte,
+    // `--break-hash <hex>` pauses when a cell gets memory with the hash,
+    // `--break-pattern <path>` pauses when a cell gets the memory of the program or saved cell,
+    // other arguments are programs to stamp into the world
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let result = match arg.as_str() {
+            "--replicator" => args
+                .next()
+                .ok_or_else(|| "--replicator expects a name".to_owned())
+                .and_then(|value| state.seed_replicator(&value)),
+            "--metrics-interval" => args
+                .next()
+                .and_then(|value| value.parse().ok())
+                .filter(|&interval| interval > 0)
+                .map(|interval| state.enable_metrics().interval = interval)
+                .ok_or_else(|| "--metrics-interval expects a positive number".to_owned()),
+            "--spatial" => {
+                state.enable_metrics().spatial = Some(SpatialOptions::default());
+                Ok(())
+            }
+            "--metrics-out" => args
+                .next()
+                .ok_or_else(|| "--metrics-out expects a path".to_owned())
+                .and_then(|path| {
+                    state.enable_metrics();
+                    let run_id = MetricsSink::default_run_id(state.world.seed);
+                    MetricsSink::create(&path, run_id, &state.world.vm_config)
+                })
+                .map(|sink| state.metrics_sink = Some(sink)),
+            "--pause-on-transition" => {
+                state.enable_metrics();
+                state.pause_on_transition = true;
+                Ok(())
+            }
+            "--lineage" => {
+                state.world.track_lineage(LineageTracker::default());
+                Ok(())
+            }
+            "--provenance" => state.world.track_provenance(),
+            "--break-hash" => args
+                .next()
+                .and_then(|value| u64::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+                .map(|hash| {
+                    let breakpoint = Breakpoint::memory_hash(&state.world, hash);
+                    state.breakpoints.push(breakpoint);
+                })
+                .ok_or_else(|| "--break-hash expects a hexadecimal hash".to_owned()),
+            "--break-pattern" => args
+                .next()
+                .ok_or_else(|| "--break-pattern expects a path".to_owned())
+                .and_then(|path| state.break_on_pattern(path.as_ref())),
+            _ => state.load_stamp(arg.as_ref()),
+        };
+
+        if let Err(err) = result {
+            eprintln!("{arg}: {err}");
+        }
+    }
 
     loop {
         if is_key_pressed(KeyCode::Escape) {
@@ -27,3 +103,31 @@ async fn main() {
         next_frame().await;
     }
 }
+
+/// Split the VM config options from the other arguments.
+fn parse_vm_config(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(VmConfig, Vec<String>), String> {
+    let mut config = VmConfig::default();
+    let mut rest = Vec::new();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} expects a value"));
+
+        match arg.as_str() {
+            "--memory-size" => {
+                config.memory_size = value()?
+                    .parse()
+                    .map_err(|err| format!("invalid memory size: {err}"))?;
+            }
+            "--bits16" => config.address_mode = AddressMode::Bits16,
+            "--revision" => config.opcode_revision = value()?.parse()?,
+            "--stack" => config.stack_mode = value()?.parse()?,
+            "--protection" => config.write_protection = Some(value()?.parse()?),
+            _ => rest.push(arg),
+        }
+    }
+    config.validate()?;
+
+    Ok((config, rest))
+}
@@ -0,0 +1,127 @@
+use crate::*;
+use nohash_hasher::IntMap;
+
+/// Abundance of distinct genomes in the population.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Census {
+    pub population: usize,
+    pub unique_genomes: usize,
+    /// The most abundant genomes, most abundant first.
+    pub top: Vec<GenomeCount>,
+    /// Probability that two random cells carry different genomes.
+    pub simpson_diversity: f64,
+    /// Shannon entropy of the genome distribution in nats.
+    pub shannon_diversity: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenomeCount {
+    pub hash: u64,
+    pub count: usize,
+    /// Index of the first cell carrying the genome.
+    pub example: usize,
+}
+
+/// What the census treats as a genome and how much it reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CensusOptions {
+    /// Hash registers together with the memory.
+    pub include_registers: bool,
+    /// Number of the most abundant genomes to report.
+    pub top: usize,
+}
+
+impl Default for CensusOptions {
+    fn default() -> Self {
+        Self {
+            include_registers: false,
+            top: 5,
+        }
+    }
+}
+
+impl Census {
+    pub fn new(cells: &[CellState], options: CensusOptions) -> Self {
+        let mut genomes = IntMap::<u64, GenomeCount>::default();
+
+        for (index, cell) in cells.iter().enumerate() {
+            let hash = genome_hash(cell, options.include_registers);
+            genomes
+                .entry(hash)
+                .or_insert(GenomeCount {
+                    hash,
+                    count: 0,
+                    example: index,
+                })
+                .count += 1;
+        }
+
+        let population = cells.len();
+        let mut simpson_sum = 0.0;
+        let mut shannon_diversity = 0.0;
+        for genome in genomes.values() {
+            let p = genome.count as f64 / population as f64;
+            simpson_sum += p * p;
+            shannon_diversity -= p * p.ln();
+        }
+
+        let mut top = genomes.into_values().collect::<Vec<_>>();
+        let unique_genomes = top.len();
+        top.sort_unstable_by_key(|genome| (std::cmp::Reverse(genome.count), genome.example));
+        top.truncate(options.top);
+
+        Self {
+            population,
+            unique_genomes,
+            top,
+            simpson_diversity: if population == 0 {
+                0.0
+            } else {
+                1.0 - simpson_sum
+            },
+            shannon_diversity,
+        }
+    }
+}
+
+/// Same as [`CellState::memory_hash`] unless registers are included.
+pub fn genome_hash(cell: &CellState, include_registers: bool) -> u64 {
+    if !include_registers {
+        return cell.memory_hash();
+    }
+
+    let registers = cell
+        .registers
+        .iter()
+        .flat_map(|register| register.to_le_bytes());
+    fnv1a_hash(cell.memory.iter().copied().chain(registers))
+}
+
+#[test]
+fn test_census() {
+    let config = VmConfig::default();
+    let program = |byte: u8| Stamp::program_cell(vec![byte], &config).unwrap();
+
+    let mut cells = vec![program(1); 6];
+    cells.extend([program(2), program(2), program(3), program(4)]);
+    cells[1].registers[0] = 1;
+
+    let census = Census::new(&cells, CensusOptions::default());
+    assert_eq!(census.population, 10);
+    assert_eq!(census.unique_genomes, 4);
+    assert_eq!(census.top[0].count, 6);
+    assert_eq!(census.top[0].example, 0);
+    assert_eq!(census.top[1].count, 2);
+    assert!((census.simpson_diversity - (1.0 - 0.36 - 0.04 - 0.01 - 0.01)).abs() < 1e-9);
+
+    let census = Census::new(
+        &cells,
+        CensusOptions {
+            include_registers: true,
+            top: 1,
+        },
+    );
+    assert_eq!(census.unique_genomes, 5);
+    assert_eq!(census.top.len(), 1);
+    assert_eq!(census.top[0].count, 5);
+}
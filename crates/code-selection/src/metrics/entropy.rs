@@ -0,0 +1,74 @@
+use flate2::{write::DeflateEncoder, Compression};
+use std::io::Write;
+
+/// Complexity of a byte string as defined by the paper, all values are in bits per byte.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HighOrderEntropy {
+    /// Shannon entropy of the byte distribution.
+    pub shannon_entropy: f64,
+    /// Size of the deflate compressed bytes, an estimate of the Kolmogorov complexity.
+    pub compressed_size: f64,
+}
+
+impl HighOrderEntropy {
+    /// Measure the concatenation of the chunks.
+    pub fn new<'a>(chunks: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        let mut counts = [0u64; 256];
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+
+        for chunk in chunks {
+            for &byte in chunk {
+                counts[byte as usize] += 1;
+            }
+            encoder
+                .write_all(chunk)
+                .expect("writing into a vec never fails");
+        }
+
+        let total = counts.iter().sum::<u64>();
+        if total == 0 {
+            return Self::default();
+        }
+
+        let shannon_entropy = counts
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / total as f64;
+                -p * p.log2()
+            })
+            .sum();
+        let compressed = encoder.finish().expect("writing into a vec never fails");
+
+        Self {
+            shannon_entropy,
+            compressed_size: compressed.len() as f64 * 8.0 / total as f64,
+        }
+    }
+
+    /// Shannon entropy minus the normalized compressed size. Close to zero for random and
+    /// uniform data, grows as the data fills with copies of complex sequences.
+    pub fn high_order(&self) -> f64 {
+        self.shannon_entropy - self.compressed_size
+    }
+}
+
+#[test]
+fn test_high_order_entropy() {
+    use ::rand::{rngs::SmallRng, Rng, SeedableRng};
+
+    let mut rng = SmallRng::seed_from_u64(0);
+    let random = (0..1 << 16).map(|_| rng.gen()).collect::<Vec<u8>>();
+    let entropy = HighOrderEntropy::new([random.as_slice()]);
+    assert!(entropy.shannon_entropy > 7.99);
+    assert!(entropy.high_order().abs() < 0.1, "{entropy:?}");
+
+    // many copies of a random program
+    let copies = vec![&random[..128]; 512];
+    let entropy = HighOrderEntropy::new(copies);
+    assert!(entropy.high_order() > 6.0, "{entropy:?}");
+
+    let zeros = HighOrderEntropy::new([[0; 1024].as_slice()]);
+    assert_eq!(zeros.shannon_entropy, 0.0);
+    assert!(zeros.high_order() < 0.0);
+}
@@ -0,0 +1,141 @@
+mod census;
+mod entropy;
+mod opcodes;
+mod sink;
+mod spatial;
+mod tokens;
+mod transition;
+
+pub use census::*;
+pub use entropy::*;
+pub use opcodes::*;
+pub use sink::*;
+pub use spatial::*;
+pub use tokens::*;
+pub use transition::*;
+
+use crate::*;
+
+/// Soup-wide metrics sampled every `interval` ticks of the world.
+#[derive(Debug, Clone)]
+pub struct MetricsRecorder {
+    pub interval: u64,
+    pub census: CensusOptions,
+    /// Spatial statistics are sampled only if set.
+    pub spatial: Option<SpatialOptions>,
+    pub samples: Vec<MetricsSample>,
+    /// Execution counters summed since the last sample.
+    pub stats: TickStats,
+    pub detector: Option<TransitionDetector>,
+    /// Detected transitions not yet handled, see [`MetricsRecorder::take_events`].
+    pub events: Vec<PhaseTransition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsSample {
+    pub tick: u64,
+    pub entropy: HighOrderEntropy,
+    pub census: Census,
+    pub spatial: Option<SpatialStats>,
+    pub opcodes: OpcodeFrequencies,
+    /// Execution counters summed over the ticks since the previous sample.
+    pub stats: TickStats,
+    /// Tracer tokens, sampled only if the world tracks provenance.
+    pub tokens: Option<TokenCensus>,
+}
+
+impl MetricsRecorder {
+    pub fn new(interval: u64) -> Self {
+        assert!(interval > 0, "metrics interval must be positive");
+
+        Self {
+            interval,
+            census: CensusOptions::default(),
+            spatial: None,
+            samples: Vec::new(),
+            stats: TickStats::default(),
+            detector: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn with_detector(mut self, detector: TransitionDetector) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+
+    /// Empty recorder with the same settings.
+    pub fn restarted(&self) -> Self {
+        Self {
+            interval: self.interval,
+            census: self.census,
+            spatial: self.spatial,
+            samples: Vec::new(),
+            stats: TickStats::default(),
+            detector: self.detector.clone().map(|detector| TransitionDetector {
+                transition: None,
+                ..detector
+            }),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<PhaseTransition> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn with_census(mut self, census: CensusOptions) -> Self {
+        self.census = census;
+        self
+    }
+
+    pub fn with_spatial(mut self, spatial: SpatialOptions) -> Self {
+        self.spatial = Some(spatial);
+        self
+    }
+
+    pub fn last_sample(&self) -> Option<&MetricsSample> {
+        self.samples.last()
+    }
+}
+
+impl World {
+    /// Measure metrics of the current state of all cells, execution counters are left empty.
+    pub fn sample_metrics(
+        &self,
+        census: CensusOptions,
+        spatial: Option<SpatialOptions>,
+    ) -> MetricsSample {
+        MetricsSample {
+            tick: self.tick_count,
+            entropy: HighOrderEntropy::new(self.cells.iter().map(|cell| cell.memory.as_slice())),
+            census: Census::new(&self.cells, census),
+            spatial: spatial.map(|spatial| SpatialStats::new(&self.cells, self.size, spatial)),
+            opcodes: OpcodeFrequencies::new(&self.cells),
+            stats: TickStats::default(),
+            tokens: TokenCensus::new(&self.cells, census.top),
+        }
+    }
+
+    /// Record a sample if the recorder is enabled and the interval has passed.
+    pub(crate) fn record_metrics(&mut self) {
+        let Some(metrics) = &mut self.metrics else {
+            return;
+        };
+        metrics.stats += self.last_tick_stats;
+        if !self.tick_count.is_multiple_of(metrics.interval) {
+            return;
+        }
+
+        let (census, spatial) = (metrics.census, metrics.spatial);
+        let mut sample = self.sample_metrics(census, spatial);
+        if let Some(metrics) = &mut self.metrics {
+            sample.stats = std::mem::take(&mut metrics.stats);
+            metrics.samples.push(sample);
+
+            if let Some(detector) = &mut metrics.detector {
+                metrics.events.extend(detector.observe(&metrics.samples));
+            }
+        }
+    }
+}
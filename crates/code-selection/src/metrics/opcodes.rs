@@ -0,0 +1,84 @@
+use crate::*;
+use std::io::Write;
+
+/// How often opcodes appear in the memory, executed instructions are counted by
+/// [`TickStats::executed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpcodeFrequencies {
+    /// Number of bytes in the memory of all cells by value.
+    pub in_memory: [u64; 256],
+}
+
+impl Default for OpcodeFrequencies {
+    fn default() -> Self {
+        Self {
+            in_memory: [0; 256],
+        }
+    }
+}
+
+impl OpcodeFrequencies {
+    pub fn new(cells: &[CellState]) -> Self {
+        let mut result = Self::default();
+        for cell in cells {
+            for &byte in &cell.memory {
+                result.in_memory[byte as usize] += 1;
+            }
+        }
+
+        result
+    }
+}
+
+/// Write opcode frequencies of the samples as CSV with one row per tick, source and key.
+///
+/// The source is `memory` with hex opcodes as keys or `executed` with instruction names.
+pub fn write_opcodes_csv(
+    samples: &[MetricsSample],
+    writer: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(writer, "tick,source,key,count")?;
+
+    for sample in samples {
+        for (opcode, count) in sample.opcodes.in_memory.iter().enumerate() {
+            writeln!(writer, "{},memory,{opcode:#04x},{count}", sample.tick)?;
+        }
+        for (name, count) in Instruction::VARIANT_NAMES
+            .iter()
+            .zip(&sample.stats.executed)
+        {
+            writeln!(writer, "{},executed,{name},{count}", sample.tick)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_opcode_frequencies() {
+    let config = VmConfig::default();
+    let source = "
+        ld a, 0x10
+        ld a, 0x20
+        nop
+    ";
+    let mut main = Stamp::program_cell(assemble(source, &config).unwrap(), &config).unwrap();
+    let mut neighbor = Stamp::program_cell(Vec::new(), &config).unwrap();
+
+    let frequencies = OpcodeFrequencies::new(&[main.clone(), neighbor.clone()]);
+    assert_eq!(frequencies.in_memory[0x18], 2);
+    assert_eq!(frequencies.in_memory[0x10], 1);
+    assert_eq!(frequencies.in_memory[0x00], 2 * 128 - 4);
+
+    let mut pair = CellPair::new(&mut main, &mut neighbor, config);
+    pair.tick();
+    let load = Instruction::VARIANT_NAMES
+        .iter()
+        .position(|name| *name == "Load");
+    let nop = Instruction::VARIANT_NAMES
+        .iter()
+        .position(|name| *name == "Nop");
+    assert_eq!(pair.stats.executed[load.unwrap()], 2);
+    assert_eq!(pair.stats.executed[nop.unwrap()], 38 - 2);
+    assert_eq!(pair.stats.executed.iter().sum::<u64>(), 38);
+}
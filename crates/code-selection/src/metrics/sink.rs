@@ -0,0 +1,385 @@
+use crate::*;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// File format of [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    /// Header row followed by a row per sample.
+    Csv,
+    /// A JSON object per line per sample.
+    JsonLines,
+}
+
+impl MetricsFormat {
+    /// Pick the format by the extension of the path, `.csv` or `.jsonl`.
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        match path.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("csv") => Ok(Self::Csv),
+            Some("jsonl" | "ndjson") => Ok(Self::JsonLines),
+            _ => Err(format!(
+                "unknown metrics format of {path}, expected .csv or .jsonl"
+            )),
+        }
+    }
+}
+
+/// Writes samples of a [`MetricsRecorder`] as they are recorded, tagged with the run id and
+/// the config hash so that runs can be told apart.
+pub struct MetricsSink<W = BufWriter<File>> {
+    pub format: MetricsFormat,
+    pub run_id: String,
+    /// [`VmConfig::stable_hash`] of the world config.
+    pub config_hash: u64,
+    writer: W,
+    /// Indices into [`metric_names`] of the written metrics, every metric by default.
+    columns: Vec<usize>,
+    /// Number of samples of the recorder already written.
+    written: usize,
+    is_header_written: bool,
+}
+
+/// Value of a single exported metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetricValue {
+    Count(u64),
+    Real(f64),
+    Missing,
+}
+
+impl MetricsSink {
+    pub fn create(path: &str, run_id: String, config: &VmConfig) -> Result<Self, String> {
+        let format = MetricsFormat::from_path(path)?;
+        let file = File::create(path).map_err(|err| format!("failed to create {path}: {err}"))?;
+
+        Self::new(BufWriter::new(file), format, run_id, config)
+    }
+
+    /// Run id of a new run of the world, unique for the seed and the current time.
+    pub fn default_run_id(seed: u64) -> String {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        format!("{seed:016x}-{}", time.as_secs())
+    }
+}
+
+impl<W: Write> MetricsSink<W> {
+    /// The run id may contain only ASCII letters, digits, `-`, `_` and `.`.
+    pub fn new(
+        writer: W,
+        format: MetricsFormat,
+        run_id: String,
+        config: &VmConfig,
+    ) -> Result<Self, String> {
+        validate_run_id(&run_id)?;
+
+        Ok(Self {
+            format,
+            run_id,
+            config_hash: config.stable_hash(),
+            writer,
+            columns: (0..metric_names().len()).collect(),
+            written: 0,
+            is_header_written: false,
+        })
+    }
+
+    /// Write only the named metrics in the given order, see [`metric_names`]. The run id and
+    /// the config hash are always written.
+    pub fn with_columns(mut self, names: &[&str]) -> Result<Self, String> {
+        let metrics = metric_names();
+        self.columns = names
+            .iter()
+            .map(|name| {
+                metrics
+                    .iter()
+                    .position(|metric| metric == name)
+                    .ok_or_else(|| format!("unknown metric `{name}`"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        match self.columns.is_empty() {
+            true => Err("no metrics selected".to_owned()),
+            false => Ok(self),
+        }
+    }
+
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Write the samples recorded since the last call and flush them.
+    pub fn write_new(&mut self, samples: &[MetricsSample]) -> std::io::Result<()> {
+        for sample in samples.get(self.written..).unwrap_or_default() {
+            self.write_sample(sample)?;
+        }
+        self.written = samples.len();
+
+        self.writer.flush()
+    }
+
+    /// Continue with the samples of a new run written to the same file.
+    pub fn restart(&mut self, run_id: String, config: &VmConfig) -> Result<(), String> {
+        validate_run_id(&run_id)?;
+
+        self.run_id = run_id;
+        self.config_hash = config.stable_hash();
+        self.written = 0;
+
+        Ok(())
+    }
+
+    pub fn write_sample(&mut self, sample: &MetricsSample) -> std::io::Result<()> {
+        let names = metric_names();
+        let values = sample_metrics(sample);
+        let metrics = self
+            .columns
+            .iter()
+            .map(|&column| (&names[column], values[column]))
+            .collect::<Vec<_>>();
+        let config_hash = format!("{:016x}", self.config_hash);
+
+        match self.format {
+            MetricsFormat::Csv => {
+                if !self.is_header_written {
+                    let names = metrics.iter().map(|(name, _)| name.as_str());
+                    let header = ["run_id", "config_hash"]
+                        .into_iter()
+                        .chain(names)
+                        .collect::<Vec<_>>();
+                    writeln!(self.writer, "{}", header.join(","))?;
+                    self.is_header_written = true;
+                }
+
+                let values = metrics.iter().map(|(_, value)| match value {
+                    MetricValue::Count(value) => value.to_string(),
+                    MetricValue::Real(value) => value.to_string(),
+                    MetricValue::Missing => String::new(),
+                });
+                let row = [self.run_id.clone(), config_hash]
+                    .into_iter()
+                    .chain(values)
+                    .collect::<Vec<_>>();
+                writeln!(self.writer, "{}", row.join(","))
+            }
+            MetricsFormat::JsonLines => {
+                let values = metrics
+                    .iter()
+                    .map(|(name, value)| match value {
+                        MetricValue::Count(value) => format!(r#""{name}":{value}"#),
+                        MetricValue::Real(value) if value.is_finite() => {
+                            format!(r#""{name}":{value}"#)
+                        }
+                        MetricValue::Real(_) | MetricValue::Missing => format!(r#""{name}":null"#),
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(
+                    self.writer,
+                    r#"{{"run_id":"{}","config_hash":"{config_hash}",{}}}"#,
+                    self.run_id,
+                    values.join(",")
+                )
+            }
+        }
+    }
+}
+
+fn validate_run_id(run_id: &str) -> Result<(), String> {
+    let is_valid = !run_id.is_empty()
+        && run_id
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_' | '.'));
+
+    match is_valid {
+        true => Ok(()),
+        false => Err(format!(
+            "run id `{run_id}` must consist of ASCII letters, digits, `-`, `_` and `.`"
+        )),
+    }
+}
+
+/// Value of an exported metric of a sample.
+type SampleMetric = fn(&MetricsSample) -> MetricValue;
+
+/// Exported metrics in the order of the columns, followed by an `executed_<instruction>`
+/// count per instruction. Optional metrics are missing if they were not sampled.
+const METRICS: [(&str, SampleMetric); 15] = [
+    ("tick", |sample| MetricValue::Count(sample.tick)),
+    ("shannon_entropy", |sample| {
+        MetricValue::Real(sample.entropy.shannon_entropy)
+    }),
+    ("compressed_size", |sample| {
+        MetricValue::Real(sample.entropy.compressed_size)
+    }),
+    ("high_order_entropy", |sample| {
+        MetricValue::Real(sample.entropy.high_order())
+    }),
+    ("unique_genomes", |sample| {
+        count(Some(sample.census.unique_genomes))
+    }),
+    ("simpson_diversity", |sample| {
+        MetricValue::Real(sample.census.simpson_diversity)
+    }),
+    ("shannon_diversity", |sample| {
+        MetricValue::Real(sample.census.shannon_diversity)
+    }),
+    ("dominant_genome_count", |sample| {
+        count(sample.census.top.first().map(|genome| genome.count))
+    }),
+    ("domains", |sample| {
+        count(sample.spatial.as_ref().map(|spatial| spatial.domains))
+    }),
+    ("largest_domain", |sample| {
+        count(
+            sample
+                .spatial
+                .as_ref()
+                .map(|spatial| spatial.largest_domain),
+        )
+    }),
+    ("boundary_length", |sample| {
+        count(
+            sample
+                .spatial
+                .as_ref()
+                .map(|spatial| spatial.boundary_length),
+        )
+    }),
+    ("surviving_tokens", |sample| {
+        count(sample.tokens.as_ref().map(|tokens| tokens.surviving))
+    }),
+    ("instructions", |sample| {
+        MetricValue::Count(sample.stats.executed.iter().sum())
+    }),
+    ("neighbor_writes", |sample| {
+        count(Some(sample.stats.neighbor_writes))
+    }),
+    ("stack_traps", |sample| {
+        count(Some(sample.stats.stack_traps))
+    }),
+];
+
+fn count(value: Option<usize>) -> MetricValue {
+    value.map_or(MetricValue::Missing, |value| {
+        MetricValue::Count(value as u64)
+    })
+}
+
+/// Names of every exported metric in the order of the columns.
+pub fn metric_names() -> Vec<String> {
+    let executed = Instruction::VARIANT_NAMES
+        .iter()
+        .map(|name| format!("executed_{}", snake_case(name)));
+
+    METRICS
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(executed)
+        .collect()
+}
+
+/// Values of every exported metric of the sample in the order of [`metric_names`].
+fn sample_metrics(sample: &MetricsSample) -> Vec<MetricValue> {
+    let executed = sample.stats.executed.map(MetricValue::Count);
+
+    METRICS
+        .iter()
+        .map(|(_, value)| value(sample))
+        .chain(executed)
+        .collect()
+}
+
+fn snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for (index, char) in name.chars().enumerate() {
+        if char.is_ascii_uppercase() && index > 0 {
+            result.push('_');
+        }
+        result.push(char.to_ascii_lowercase());
+    }
+
+    result
+}
+
+#[test]
+fn test_metrics_sink() {
+    let config = VmConfig::default();
+    let mut world = World::with_seed(AreaSize::splat(4), config, 0);
+    world.metrics = Some(MetricsRecorder::new(2));
+
+    let mut csv =
+        MetricsSink::new(Vec::new(), MetricsFormat::Csv, "run-1".to_owned(), &config).unwrap();
+    let mut json = MetricsSink::new(
+        Vec::new(),
+        MetricsFormat::JsonLines,
+        "run-1".to_owned(),
+        &config,
+    )
+    .unwrap();
+
+    for _ in 0..4 {
+        world.tick();
+        let samples = &world.metrics.as_ref().unwrap().samples;
+        csv.write_new(samples).unwrap();
+        json.write_new(samples).unwrap();
+    }
+
+    let csv = String::from_utf8(csv.writer().clone()).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("run_id,config_hash,tick,shannon_entropy,"));
+    assert!(lines[0].contains(",stack_traps,executed_nop,"));
+    assert!(lines[0].ends_with(",executed_right_shift,executed_compare,executed_replicate,executed_protect,executed_unprotect,executed_random,executed_sense"));
+    let config_hash = format!("{:016x}", config.stable_hash());
+    assert!(lines[2].starts_with(&format!("run-1,{config_hash},4,")));
+    assert_eq!(lines[1].split(',').count(), lines[0].split(',').count());
+
+    let json = String::from_utf8(json.writer().clone()).unwrap();
+    let lines = json.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!(
+        r#"{{"run_id":"run-1","config_hash":"{config_hash}","tick":2,"#
+    )));
+    assert!(lines[0].contains(r#""domains":null,"#));
+    assert!(lines[0].contains(r#""instructions":"#));
+
+    assert!(MetricsSink::new(Vec::new(), MetricsFormat::Csv, "a,b".to_owned(), &config).is_err());
+}
+
+#[test]
+fn test_metrics_sink_columns() {
+    let config = VmConfig {
+        opcode_revision: OpcodeRevision::V2,
+        ..Default::default()
+    };
+    let mut world = World::with_seed(AreaSize::splat(16), config, 0);
+    world.metrics = Some(MetricsRecorder::new(64));
+    for _ in 0..64 {
+        world.tick();
+    }
+
+    let sink =
+        MetricsSink::new(Vec::new(), MetricsFormat::Csv, "run-1".to_owned(), &config).unwrap();
+    let mut sink = sink
+        .with_columns(&["tick", "stack_traps", "executed_random"])
+        .unwrap();
+    sink.write_new(&world.metrics.as_ref().unwrap().samples)
+        .unwrap();
+
+    let csv = String::from_utf8(sink.writer().clone()).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "run_id,config_hash,tick,stack_traps,executed_random"
+    );
+    let row = lines[1].split(',').collect::<Vec<_>>();
+    assert_eq!(row[2], "64");
+    assert_eq!(row[3], "0");
+    assert_ne!(row[4], "0");
+
+    let sink =
+        MetricsSink::new(Vec::new(), MetricsFormat::Csv, "run-1".to_owned(), &config).unwrap();
+    assert!(sink.with_columns(&["tick", "entropy"]).is_err());
+}
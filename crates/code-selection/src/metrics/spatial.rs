@@ -0,0 +1,215 @@
+use crate::*;
+use rayon::prelude::*;
+
+/// Layout of genomes on the grid: domains of similar cells and how similarity decays with
+/// distance.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SpatialStats {
+    /// Number of connected domains of similar cells.
+    pub domains: usize,
+    pub largest_domain: usize,
+    /// Size of the domain containing a random cell.
+    pub weighted_domain_size: f64,
+    /// Number of adjacent cell pairs lying in different domains.
+    pub boundary_length: usize,
+    /// Similarity at distances 1, 2, ... relative to random pairs of cells: 1 for identical
+    /// neighbors, 0 for neighbors no more similar than random cells.
+    pub autocorrelation: Vec<f64>,
+}
+
+/// How spatial statistics group cells into domains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialOptions {
+    /// Share of equal bytes making adjacent cells belong to the same domain, 1 for identical.
+    pub similarity_threshold: f64,
+    /// Largest distance of the autocorrelation, limited by half of the world size.
+    pub max_distance: usize,
+}
+
+impl Default for SpatialOptions {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: 0.9,
+            max_distance: 8,
+        }
+    }
+}
+
+/// Directions to adjacent cells, each adjacent pair is visited once.
+const ADJACENT: [Direction; 2] = [Direction::Right, Direction::Up];
+
+impl SpatialStats {
+    pub fn new(cells: &[CellState], size: AreaSize, options: SpatialOptions) -> Self {
+        assert_eq!(cells.len(), size.area(), "cells do not fill the world");
+
+        let mut domains = DisjointSet::new(cells.len());
+        let mut edges = Vec::with_capacity(cells.len() * ADJACENT.len());
+        for index in 0..cells.len() {
+            for direction in ADJACENT {
+                let other = wrapping_neighbor(size, index, direction, 1);
+                let similar =
+                    similarity(&cells[index], &cells[other]) >= options.similarity_threshold;
+                if similar {
+                    domains.union(index, other);
+                }
+                edges.push((index, other));
+            }
+        }
+
+        let mut sizes = vec![0; cells.len()];
+        for index in 0..cells.len() {
+            sizes[domains.find(index)] += 1;
+        }
+        let sizes = sizes
+            .into_iter()
+            .filter(|size| *size > 0)
+            .collect::<Vec<_>>();
+
+        let boundary_length = edges
+            .into_iter()
+            .filter(|&(a, b)| domains.find(a) != domains.find(b))
+            .count();
+
+        let max_distance = options
+            .max_distance
+            .min(size.width / 2)
+            .min(size.height / 2);
+
+        Self {
+            domains: sizes.len(),
+            largest_domain: sizes.iter().copied().max().unwrap_or(0),
+            weighted_domain_size: sizes.iter().map(|size| size * size).sum::<usize>() as f64
+                / cells.len().max(1) as f64,
+            boundary_length,
+            autocorrelation: autocorrelation(cells, size, max_distance),
+        }
+    }
+
+    /// Distance at which the autocorrelation falls below 1/e, `None` if it stays above.
+    pub fn correlation_length(&self) -> Option<usize> {
+        self.autocorrelation
+            .iter()
+            .position(|correlation| *correlation < std::f64::consts::E.recip())
+            .map(|index| index + 1)
+    }
+}
+
+/// Share of equal bytes at the same addresses.
+pub fn similarity(a: &CellState, b: &CellState) -> f64 {
+    let equal = a
+        .memory
+        .iter()
+        .zip(&b.memory)
+        .filter(|(a, b)| a == b)
+        .count();
+
+    equal as f64 / a.memory.len().max(b.memory.len()).max(1) as f64
+}
+
+fn autocorrelation(cells: &[CellState], size: AreaSize, max_distance: usize) -> Vec<f64> {
+    // expected similarity of two random cells
+    let memory_size = cells.first().map_or(0, |cell| cell.memory.len());
+    let baseline = (0..memory_size)
+        .map(|address| {
+            let mut counts = [0usize; 256];
+            for cell in cells {
+                counts[cell.memory[address] as usize] += 1;
+            }
+            counts
+                .iter()
+                .map(|count| (*count as f64 / cells.len() as f64).powi(2))
+                .sum::<f64>()
+        })
+        .sum::<f64>()
+        / memory_size.max(1) as f64;
+
+    (1..=max_distance)
+        .map(|distance| {
+            let total = (0..cells.len())
+                .into_par_iter()
+                .map(|index| {
+                    ADJACENT
+                        .iter()
+                        .map(|&direction| {
+                            let other = wrapping_neighbor(size, index, direction, distance);
+                            similarity(&cells[index], &cells[other])
+                        })
+                        .sum::<f64>()
+                })
+                .sum::<f64>();
+            let mean = total / (cells.len() * ADJACENT.len()) as f64;
+
+            if baseline < 1.0 {
+                (mean - baseline) / (1.0 - baseline)
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Index of the cell `distance` steps away in the direction, wrapping around the world edges.
+fn wrapping_neighbor(size: AreaSize, index: usize, direction: Direction, distance: usize) -> usize {
+    let position = size.index_to_coords(index);
+    let (dx, dy) = direction.to_offset();
+
+    let x = (position.x as i64 + dx as i64 * distance as i64).rem_euclid(size.width as i64);
+    let y = (position.y as i64 + dy as i64 * distance as i64).rem_euclid(size.height as i64);
+
+    size.coords_to_index(RelativePosition::new(x as u32, y as u32))
+}
+
+/// Union-find over cell indices.
+struct DisjointSet {
+    parents: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+}
+
+#[test]
+fn test_spatial_stats() {
+    use ::rand::{rngs::SmallRng, SeedableRng};
+
+    let config = VmConfig::default();
+    let size = AreaSize::new(8, 4);
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    // left half is a single genome, right half is random
+    let genome = CellState::random(&config, &mut rng);
+    let cells = (0..size.area())
+        .map(|index| match size.index_to_coords(index).x < 4 {
+            true => genome.clone(),
+            false => CellState::random(&config, &mut rng),
+        })
+        .collect::<Vec<_>>();
+
+    let stats = SpatialStats::new(&cells, size, SpatialOptions::default());
+    assert_eq!(stats.domains, 1 + 16);
+    assert_eq!(stats.largest_domain, 16);
+    assert_eq!(stats.weighted_domain_size, (16.0 * 16.0 + 16.0) / 32.0);
+    // edges inside the random half and both seams of the wrapped world
+    assert_eq!(stats.boundary_length, 16 + 12 + 2 * 4);
+    assert_eq!(stats.autocorrelation.len(), 2);
+    assert!(stats.autocorrelation[0] > stats.autocorrelation[1]);
+    assert!(stats.autocorrelation[1] > 0.0);
+    assert_eq!(stats.correlation_length(), Some(1));
+}
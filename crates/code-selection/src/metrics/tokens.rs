@@ -0,0 +1,104 @@
+use crate::*;
+
+/// Survival of the tracer tokens given to memory bytes by [`World::track_provenance`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TokenCensus {
+    /// Number of tokens given out, one per byte of the world when tracking started.
+    pub original: usize,
+    /// Number of distinct tokens still held by any memory byte.
+    pub surviving: usize,
+    /// Number of memory bytes holding a token.
+    pub bytes: usize,
+    /// The most copied tokens, most copied first.
+    pub top: Vec<TokenCount>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCount {
+    pub token: u32,
+    pub count: usize,
+}
+
+impl TokenCount {
+    /// Index of the cell and the address the token was given to.
+    pub fn origin(&self, memory_size: usize) -> (usize, usize) {
+        let token = self.token as usize;
+        (token / memory_size, token % memory_size)
+    }
+}
+
+impl TokenCensus {
+    /// Count tokens of the cells, `None` if they do not track provenance.
+    pub fn new(cells: &[CellState], top: usize) -> Option<Self> {
+        let memory_size = cells.first()?.provenance.as_ref()?.memory.len();
+        let original = cells.len() * memory_size;
+
+        let mut counts = vec![0; original];
+        let mut bytes = 0;
+        for cell in cells {
+            for byte in &cell.provenance.as_ref()?.memory {
+                if let Some(count) = counts.get_mut(byte.token as usize) {
+                    *count += 1;
+                    bytes += 1;
+                }
+            }
+        }
+
+        let mut tokens = counts
+            .into_iter()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .map(|(token, count)| TokenCount {
+                token: token as u32,
+                count,
+            })
+            .collect::<Vec<_>>();
+        let surviving = tokens.len();
+
+        tokens.sort_unstable_by_key(|token| (std::cmp::Reverse(token.count), token.token));
+        tokens.truncate(top);
+
+        Some(Self {
+            original,
+            surviving,
+            bytes,
+            top: tokens,
+        })
+    }
+}
+
+#[test]
+fn test_token_census() {
+    let config = VmConfig::default();
+    let mut world = World::with_seed(AreaSize::new(2, 2), config, 0);
+    assert_eq!(TokenCensus::new(&world.cells, 3), None);
+
+    world.track_provenance().unwrap();
+    let census = TokenCensus::new(&world.cells, 3).unwrap();
+    assert_eq!(census.original, 4 * 128);
+    assert_eq!(census.surviving, 4 * 128);
+    assert_eq!(census.top[0].count, 1);
+
+    // the first byte of cell 3 is copied over the whole cell 0, other tokens of cell 0 die
+    let copied = world.cells[3].provenance.as_ref().unwrap().memory[0];
+    world.cells[0]
+        .provenance
+        .as_mut()
+        .unwrap()
+        .memory
+        .fill(copied);
+    // computed bytes hold no token
+    world.cells[1].provenance.as_mut().unwrap().memory[5] = Provenance::computed(1, 3);
+
+    let census = TokenCensus::new(&world.cells, 3).unwrap();
+    assert_eq!(census.surviving, 3 * 128 - 1);
+    assert_eq!(census.bytes, 4 * 128 - 1);
+    assert_eq!(
+        census.top[0],
+        TokenCount {
+            token: 3 * 128,
+            count: 129
+        }
+    );
+    assert_eq!(census.top[0].origin(128), (3, 0));
+}
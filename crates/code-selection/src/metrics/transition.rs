@@ -0,0 +1,163 @@
+use crate::*;
+use std::fmt;
+
+/// Watches sampled metrics for the moment self-replicators take over the soup.
+///
+/// Replicators fill the soup with copies, which makes it far more compressible and lets a
+/// single genome take a noticeable share of the population. Either signal flags the
+/// transition, the detector fires only once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionDetector {
+    /// Drop of the compressed size in bits per byte from the highest of the last `window`
+    /// samples, `None` disables the signal.
+    pub compressed_drop: Option<f64>,
+    /// Number of samples the compressed size drop is measured over.
+    pub window: usize,
+    /// Share of the population carrying the most abundant genome, `None` disables the signal.
+    pub dominant_share: Option<f64>,
+    /// The detected transition.
+    pub transition: Option<PhaseTransition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhaseTransition {
+    pub tick: u64,
+    pub reason: TransitionReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransitionReason {
+    /// Compressed size in bits per byte dropped from `from` to `to`.
+    CompressedDrop { from: f64, to: f64 },
+    /// The most abundant genome is carried by the given share of the population.
+    DominantGenome { share: f64 },
+}
+
+impl Default for TransitionDetector {
+    fn default() -> Self {
+        Self {
+            compressed_drop: Some(1.0),
+            window: 8,
+            dominant_share: Some(0.1),
+            transition: None,
+        }
+    }
+}
+
+impl TransitionDetector {
+    /// Check the last sample against the previous ones. Returns the transition the first
+    /// time it is detected.
+    pub fn observe(&mut self, samples: &[MetricsSample]) -> Option<PhaseTransition> {
+        if self.transition.is_some() {
+            return None;
+        }
+        let sample = samples.last()?;
+
+        let reason = self
+            .check_compressed_drop(samples)
+            .or_else(|| self.check_dominant_share(sample))?;
+
+        let transition = PhaseTransition {
+            tick: sample.tick,
+            reason,
+        };
+        self.transition = Some(transition);
+
+        Some(transition)
+    }
+
+    fn check_compressed_drop(&self, samples: &[MetricsSample]) -> Option<TransitionReason> {
+        let drop = self.compressed_drop?;
+        let (sample, previous) = samples.split_last()?;
+
+        let to = sample.entropy.compressed_size;
+        let from = previous
+            .iter()
+            .rev()
+            .take(self.window)
+            .map(|sample| sample.entropy.compressed_size)
+            .max_by(f64::total_cmp)?;
+
+        (from - to >= drop).then_some(TransitionReason::CompressedDrop { from, to })
+    }
+
+    fn check_dominant_share(&self, sample: &MetricsSample) -> Option<TransitionReason> {
+        let threshold = self.dominant_share?;
+        let census = &sample.census;
+
+        let share = census.top.first()?.count as f64 / census.population.max(1) as f64;
+        (share >= threshold).then_some(TransitionReason::DominantGenome { share })
+    }
+}
+
+impl fmt::Display for PhaseTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "replicators took over at tick {}: ", self.tick)?;
+
+        match self.reason {
+            TransitionReason::CompressedDrop { from, to } => {
+                write!(
+                    f,
+                    "compressed size dropped from {from:.3} to {to:.3} bits per byte"
+                )
+            }
+            TransitionReason::DominantGenome { share } => {
+                write!(f, "{:.1}% of cells carry the same genome", share * 100.0)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_transition_detector() {
+    let sample = |tick: u64, compressed_size: f64, dominant: usize| MetricsSample {
+        tick,
+        entropy: HighOrderEntropy {
+            shannon_entropy: 8.0,
+            compressed_size,
+        },
+        census: Census {
+            population: 100,
+            unique_genomes: 100 - dominant + 1,
+            top: vec![GenomeCount {
+                hash: 0,
+                count: dominant,
+                example: 0,
+            }],
+            ..Default::default()
+        },
+        spatial: None,
+        opcodes: OpcodeFrequencies::default(),
+        stats: TickStats::default(),
+        tokens: None,
+    };
+
+    let mut detector = TransitionDetector::default();
+    let mut samples = Vec::new();
+    for tick in 0..20 {
+        samples.push(sample(tick, 8.0 - tick as f64 * 0.05, 1));
+        assert_eq!(detector.observe(&samples), None);
+    }
+
+    samples.push(sample(20, 6.0, 1));
+    let transition = detector.observe(&samples).unwrap();
+    assert_eq!(transition.tick, 20);
+    assert!(matches!(
+        transition.reason,
+        TransitionReason::CompressedDrop { to: 6.0, .. }
+    ));
+    assert_eq!(detector.observe(&samples), None);
+
+    let mut detector = TransitionDetector {
+        compressed_drop: None,
+        ..Default::default()
+    };
+    assert_eq!(detector.observe(&samples), None);
+    samples.push(sample(21, 6.0, 15));
+    assert_eq!(
+        detector
+            .observe(&samples)
+            .map(|transition| transition.reason),
+        Some(TransitionReason::DominantGenome { share: 0.15 })
+    );
+}
@@ -8,16 +8,8 @@ pub struct Position {
 
 impl std::hash::Hash for Position {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        // SAFETY: i32 and u32 have the same size and alignment.
-        let (x, y) = unsafe {
-            (
-                std::mem::transmute::<i32, u32>(self.x),
-                std::mem::transmute::<i32, u32>(self.y),
-            )
-        };
-
-        let x = x as u64;
-        let y = y as u64;
+        let x = self.x.cast_unsigned() as u64;
+        let y = self.y.cast_unsigned() as u64;
 
         let data = (y << 32) | x;
 
@@ -0,0 +1,22 @@
+; Copies itself into the neighbor one byte per iteration and starts over when done.
+;
+; Jumps push the return address, so the stack is moved to the end of the own memory
+; where it grows down without reaching the program.
+start:  ld a, memory_size
+        ld sp, a
+        ld e, a         ; e = offset of the neighbor memory
+        ld a, 1
+        ld d, a
+        ld a, 0
+        ld b, a         ; b = index of the copied byte
+loop:   ld a, b
+        ld c, [a]
+        add a, e
+        ld [a], c
+        ld a, b
+        add a, d
+        ld b, a
+        cmp a, end
+        jz start        ; start is 0, so the immediate runs as a nop when not taken
+        jmp loop
+end:
@@ -0,0 +1,23 @@
+; Same as copy_loop, but protects every byte of the program and of its copy before writing
+; it, so other cells can't overwrite either when write protection is enabled. A partial
+; copy keeps its bytes until the next partner turn completes it. Needs revision 1.
+start:  ld a, memory_size
+        ld sp, a
+        ld e, a         ; e = offset of the neighbor memory
+        ld a, 1
+        ld d, a
+        ld a, 0
+        ld b, a         ; b = index of the copied byte
+loop:   ld a, b
+        prot [a]
+        ld c, [a]
+        add a, e
+        prot [a]
+        ld [a], c
+        ld a, b
+        add a, d
+        ld b, a
+        cmp a, end
+        jz start        ; start is 0, so the immediate runs as a nop when not taken
+        jmp loop
+end:
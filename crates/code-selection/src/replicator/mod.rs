@@ -0,0 +1,213 @@
+use crate::*;
+use ::rand::Rng;
+
+/// Hand-written self-replicator shipped with the crate.
+///
+/// Every replicator starts at address 0 with zeroed registers, copies its program to the
+/// start of the neighbor memory and jumps back to address 0 to copy itself again.
+///
+/// A copy takes several turns of the pair. The partner of a cell changes every world tick,
+/// so in the world every partner gets only a part of each copy. Only `guarded_copy_loop`
+/// keeps the parts by protecting the copied bytes, and spreads when protection is enabled.
+#[derive(Debug, Clone, Copy)]
+pub struct Replicator {
+    pub name: &'static str,
+    pub source: &'static str,
+    pub min_revision: OpcodeRevision,
+    /// Copies by pushing its bytes, so the stack must be in the memory with byte sized slots.
+    pub pushes_bytes: bool,
+}
+
+pub const REPLICATORS: &[Replicator] = &[
+    Replicator {
+        name: "copy_loop",
+        source: include_str!("copy_loop.asm"),
+        min_revision: OpcodeRevision::V0,
+        pushes_bytes: false,
+    },
+    Replicator {
+        name: "stack_copy",
+        source: include_str!("stack_copy.asm"),
+        min_revision: OpcodeRevision::V0,
+        pushes_bytes: true,
+    },
+    Replicator {
+        name: "guarded_copy_loop",
+        source: include_str!("guarded_copy_loop.asm"),
+        min_revision: OpcodeRevision::V1,
+        pushes_bytes: false,
+    },
+];
+
+impl Replicator {
+    /// Fraction of cells replaced when seeding the world if not specified.
+    pub const DEFAULT_SEED_FRACTION: f64 = 0.01;
+
+    pub fn find(name: &str) -> Option<&'static Self> {
+        REPLICATORS
+            .iter()
+            .find(|replicator| replicator.name == name)
+    }
+
+    /// Parse `name` or `name=fraction` of the cells to seed with the replicator.
+    pub fn parse_seeding(value: &str) -> Result<(&'static Self, f64), String> {
+        let (name, fraction) = match value.split_once('=') {
+            Some((name, fraction)) => (
+                name,
+                fraction
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|fraction| (0.0..=1.0).contains(fraction))
+                    .ok_or_else(|| format!("invalid fraction `{fraction}`"))?,
+            ),
+            None => (value, Self::DEFAULT_SEED_FRACTION),
+        };
+        let replicator = Self::find(name).ok_or_else(|| format!("unknown replicator `{name}`"))?;
+
+        Ok((replicator, fraction))
+    }
+
+    /// Whether the replicator works with the config.
+    pub fn supports(&self, config: &VmConfig) -> bool {
+        if config.opcode_revision < self.min_revision {
+            return false;
+        }
+
+        match config.stack_mode {
+            StackMode::Wrap => !self.pushes_bytes || config.address_mode == AddressMode::Bits8,
+            StackMode::Separate { .. } => !self.pushes_bytes,
+            // the stack pointer is set to the memory size
+            StackMode::Trap { size } => !self.pushes_bytes && config.memory_size <= size,
+        }
+    }
+
+    pub fn program(&self, config: &VmConfig) -> Result<Vec<u8>, String> {
+        if !self.supports(config) {
+            return Err(format!(
+                "replicator {} doesn't support {config:?}",
+                self.name
+            ));
+        }
+
+        assemble(self.source, config).map_err(|err| format!("{}: {err}", self.name))
+    }
+
+    pub fn stamp(&self, config: &VmConfig) -> Result<Stamp, String> {
+        Ok(Stamp {
+            name: self.name.to_owned(),
+            cell: Stamp::program_cell(self.program(config)?, config)?,
+        })
+    }
+}
+
+impl World {
+    /// Replace the given fraction of cells with the replicator. Returns the number of seeded cells.
+    pub fn seed_replicator(
+        &mut self,
+        replicator: &Replicator,
+        fraction: f64,
+        rng: &mut impl Rng,
+    ) -> Result<usize, String> {
+        let stamp = replicator.stamp(&self.vm_config)?;
+        let corner = RelativePosition::new(self.size.width as u32 - 1, self.size.height as u32 - 1);
+
+        Ok(self.stamp_area(
+            RelativePosition::new(0, 0),
+            corner,
+            &stamp.cell,
+            fraction,
+            rng,
+        ))
+    }
+}
+
+#[test]
+fn test_replicators_copy_themselves() {
+    use ::rand::{rngs::SmallRng, SeedableRng};
+
+    let configs = [
+        VmConfig::default(),
+        VmConfig {
+            opcode_revision: OpcodeRevision::V3,
+            write_protection: Some(ProtectionPenalty::DropWrite),
+            ..Default::default()
+        },
+        VmConfig {
+            opcode_revision: OpcodeRevision::V3,
+            stack_mode: StackMode::Separate { size: 16 },
+            ..Default::default()
+        },
+        VmConfig {
+            memory_size: 300,
+            address_mode: AddressMode::Bits16,
+            opcode_revision: OpcodeRevision::V3,
+            ..Default::default()
+        },
+    ];
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    for replicator in REPLICATORS {
+        for config in configs.iter().filter(|config| replicator.supports(config)) {
+            let program = replicator.program(config).unwrap();
+            let zeroed = Stamp::program_cell(Vec::new(), config).unwrap();
+
+            for mut neighbor in [zeroed, CellState::random(config, &mut rng)] {
+                let mut main = Stamp::program_cell(program.clone(), config).unwrap();
+
+                // every turn runs with the budget of a world tick, only registers carry over
+                let mut turns = 0;
+                while neighbor.memory[..program.len()] != program {
+                    CellPair::new(&mut main, &mut neighbor, *config).tick();
+                    turns += 1;
+                    assert!(
+                        turns <= 12,
+                        "{} didn't copy itself in 12 turns {config:?}",
+                        replicator.name
+                    );
+                }
+
+                assert_eq!(main.memory[..program.len()], program, "{}", replicator.name);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_replicator_spreads_in_world() {
+    use ::rand::{rngs::SmallRng, SeedableRng};
+
+    let replicator = Replicator::find("guarded_copy_loop").unwrap();
+    let config = VmConfig {
+        opcode_revision: OpcodeRevision::V1,
+        write_protection: Some(ProtectionPenalty::DropWrite),
+        ..Default::default()
+    };
+    let program = replicator.program(&config).unwrap();
+    let empty = Stamp::program_cell(Vec::new(), &config).unwrap();
+    let copies = |world: &World| {
+        world
+            .cells
+            .iter()
+            .filter(|cell| cell.memory[..program.len()] == program)
+            .count()
+    };
+
+    // start from empty cells, random ones may overwrite the seeds before they are protected
+    for seed in 0..4 {
+        let mut world = World::with_seed(AreaSize::splat(16), config, seed);
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let corner = RelativePosition::new(15, 15);
+        world.stamp_area(RelativePosition::new(0, 0), corner, &empty, 1.0, &mut rng);
+        let seeded = world.seed_replicator(replicator, 0.1, &mut rng).unwrap();
+
+        for _ in 0..512 {
+            world.tick();
+        }
+
+        assert!(
+            copies(&world) > seeded + seeded / 4,
+            "{seeded} seeded copies grew only to {}",
+            copies(&world)
+        );
+    }
+}
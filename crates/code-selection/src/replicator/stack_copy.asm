@@ -0,0 +1,22 @@
+; Copies itself backwards into the neighbor by pushing its own bytes, which is faster than
+; a load and a store. Needs 8-bit registers (stack slots are one byte) and the wrap stack.
+;
+; The stack pointer starts right past the end of the copy. Every jump pushes the return
+; address onto the next slot, so the loop pops it before pushing the copied byte over it.
+start:  ld a, memory_size
+        ld e, a
+        ld a, 1
+        ld d, a
+        ld a, end
+        ld b, a         ; b = index of the last copied byte + 1
+        add a, e
+        ld sp, a
+        jmp loop
+loop:   pop c           ; drop the return address of the previous jump
+        ld a, b
+        sub a, d
+        ld b, a
+        push [a]        ; neighbor[b] = own[b], z when the first byte is copied
+        jz start        ; start is 0, so the immediate runs as a nop when not taken
+        jmp loop
+end:
@@ -0,0 +1,7 @@
+/// FNV-1a hash of the bytes. Unlike [`std::hash::DefaultHasher`] it stays the same across
+/// builds, so it is used for hashes shown to users or exported.
+pub fn fnv1a_hash(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
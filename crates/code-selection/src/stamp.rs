@@ -0,0 +1,179 @@
+use crate::*;
+use ::rand::Rng;
+use std::path::Path;
+
+/// Cell state painted onto cells of the world.
+#[derive(Debug, Clone)]
+pub struct Stamp {
+    pub name: String,
+    pub cell: CellState,
+}
+
+impl Stamp {
+    /// Load assembly source (`.asm`), a cell saved with [`CellState::to_bytes`] (`.cell`)
+    /// or a raw memory image (anything else). Programs start with zeroed registers.
+    pub fn load(path: &Path, config: &VmConfig) -> Result<Self, String> {
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let error = |err: String| format!("{}: {err}", path.display());
+
+        let bytes = std::fs::read(path).map_err(|err| error(err.to_string()))?;
+
+        let cell = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("cell") => CellState::from_bytes(&bytes, config).map_err(error)?,
+            Some("asm") => {
+                let source = String::from_utf8(bytes).map_err(|err| error(err.to_string()))?;
+                let memory = assemble(&source, config).map_err(|err| error(err.to_string()))?;
+                Self::program_cell(memory, config).map_err(error)?
+            }
+            _ => Self::program_cell(bytes, config).map_err(error)?,
+        };
+
+        Ok(Self { name, cell })
+    }
+
+    /// Cell with the program at the start of the memory and zeroed registers.
+    pub fn program_cell(mut memory: Vec<u8>, config: &VmConfig) -> Result<CellState, String> {
+        if memory.len() > config.memory_size {
+            return Err(format!(
+                "program is {} bytes long, memory size is {}",
+                memory.len(),
+                config.memory_size
+            ));
+        }
+        memory.resize(config.memory_size, 0);
+
+        Ok(CellState {
+            memory,
+            registers: [0; CellState::REGISTERS_COUNT],
+            tags: Vec::new(),
+            stack: Vec::new(),
+            lineage: None,
+            provenance: None,
+        })
+    }
+}
+
+/// How a stamp is painted with the mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StampTool {
+    /// Stamp every cell under the brush.
+    Fill,
+    /// Stamp a random fraction of cells under the brush.
+    Spray,
+    /// Stamp every cell of the rectangle dragged with the mouse.
+    Rectangle,
+}
+
+impl World {
+    /// Replace the cell with a copy of the memory and registers of the stamp.
+    ///
+    /// The stamped cell is a new founder: it has no protection tags or separate stack, and
+    /// if lineages are tracked it starts its own one. If provenance is tracked, the stamped
+    /// values originate from the cell at the current tick and carry no tracer tokens.
+    pub fn stamp(&mut self, index: usize, stamp: &CellState) {
+        let tracks_provenance = self.cells[index].provenance.is_some();
+        let tracks_lineage = self.lineage.is_some();
+        let cell = &mut self.cells[index];
+        cell.clone_from(stamp);
+        cell.tags.clear();
+        cell.stack.clear();
+        cell.lineage = tracks_lineage.then(|| Lineage {
+            genome: cell.memory_hash(),
+            parent: None,
+        });
+
+        if tracks_provenance {
+            let origin = Provenance::computed(index as u32, self.tick_count);
+            let shadow = CellProvenance::new(origin, stamp.memory.len());
+            cell.provenance = Some(Box::new(shadow));
+        }
+    }
+
+    /// Stamp cells of the rectangle between two corners (inclusive), each with the given
+    /// probability. Returns the number of stamped cells.
+    pub fn stamp_area(
+        &mut self,
+        corner0: RelativePosition,
+        corner1: RelativePosition,
+        stamp: &CellState,
+        fraction: f64,
+        rng: &mut impl Rng,
+    ) -> usize {
+        let mut stamped = 0;
+
+        for y in corner0.y.min(corner1.y)..=corner0.y.max(corner1.y) {
+            for x in corner0.x.min(corner1.x)..=corner0.x.max(corner1.x) {
+                let pos = RelativePosition::new(x, y);
+                if !self.size.contains(pos) || !rng.gen_bool(fraction) {
+                    continue;
+                }
+
+                self.stamp(self.size.coords_to_index(pos), stamp);
+                stamped += 1;
+            }
+        }
+
+        stamped
+    }
+}
+
+#[test]
+fn test_stamp_saved_cell() {
+    use ::rand::{rngs::SmallRng, SeedableRng};
+
+    let config = VmConfig::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut world = World::with_seed(AreaSize::splat(8), config, 0);
+
+    let cell = CellState::random(&config, &mut rng);
+    let saved = CellState::from_bytes(&cell.to_bytes(&config), &config).unwrap();
+    assert_eq!(saved.memory, cell.memory);
+    assert_eq!(saved.registers, cell.registers);
+
+    let corner0 = RelativePosition::new(6, 1);
+    let corner1 = RelativePosition::new(9, 2);
+    assert_eq!(world.stamp_area(corner0, corner1, &saved, 1.0, &mut rng), 4);
+    assert_eq!(
+        world.cells[world.size.coords_to_index(corner0)].memory,
+        cell.memory
+    );
+}
+
+#[test]
+fn test_stamp_founder() {
+    let config = VmConfig {
+        write_protection: Some(ProtectionPenalty::DropWrite),
+        stack_mode: StackMode::Separate { size: 4 },
+        ..Default::default()
+    };
+    let mut world = World::with_seed(AreaSize::splat(4), config, 0);
+    world.track_lineage(LineageTracker::default());
+
+    // a cell copied from another world position
+    let mut copied = world.cells[1].clone();
+    *copied.get_tag_mut(0) = MemoryTag {
+        owner: 1,
+        is_protected: true,
+    };
+    copied.stack = vec![1, 2, 3, 4];
+    copied.lineage = Some(Lineage {
+        genome: 1,
+        parent: Some(2),
+    });
+
+    world.stamp(5, &copied);
+    let cell = &world.cells[5];
+    assert_eq!(cell.memory, copied.memory);
+    assert!(cell.tags.is_empty());
+    assert!(cell.stack.is_empty());
+    assert_eq!(
+        cell.lineage,
+        Some(Lineage {
+            genome: copied.memory_hash(),
+            parent: None,
+        })
+    );
+}
@@ -0,0 +1,25 @@
+use crate::*;
+
+/// Counters collected while executing pairs during a single world tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TickStats {
+    /// Number of stack overflows and underflows in [`crate::StackMode::Trap`].
+    pub stack_traps: usize,
+    /// Number of bytes written by main cells into their neighbors.
+    pub neighbor_writes: usize,
+    /// Index of the first main cell which wrote into its neighbor.
+    pub first_neighbor_writer: Option<u32>,
+    /// Number of executed instructions by [`Instruction::variant_index`].
+    pub executed: [u64; Instruction::VARIANT_COUNT],
+}
+
+impl std::ops::AddAssign for TickStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.stack_traps += rhs.stack_traps;
+        self.neighbor_writes += rhs.neighbor_writes;
+        self.first_neighbor_writer = self.first_neighbor_writer.or(rhs.first_neighbor_writer);
+        for (executed, rhs) in self.executed.iter_mut().zip(rhs.executed) {
+            *executed += rhs;
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::*;
+use nohash_hasher::IntSet;
+use std::collections::VecDeque;
+use std::io::Write;
+
+/// Records every executed step of selected pairs into a bounded ring buffer.
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    /// World indices of traced cells. A pair is traced if any of its cells is selected.
+    pub cells: IntSet<u32>,
+    /// Maximum number of stored steps, the oldest steps are dropped first.
+    pub capacity: usize,
+    pub entries: VecDeque<TraceEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub tick: u64,
+    pub main_index: u32,
+    pub neighbor_index: u32,
+    pub step: StepRecord,
+}
+
+impl Tracer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            cells: IntSet::default(),
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn with_cells(mut self, cells: impl IntoIterator<Item = u32>) -> Self {
+        self.cells.extend(cells);
+        self
+    }
+
+    #[inline(always)]
+    pub fn is_traced(&self, context: &PairContext) -> bool {
+        self.cells.contains(&context.main_index) || self.cells.contains(&context.neighbor_index)
+    }
+
+    pub fn record(&mut self, context: &PairContext, step: StepRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(TraceEntry {
+            tick: context.tick,
+            main_index: context.main_index,
+            neighbor_index: context.neighbor_index,
+            step,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Write stored steps as JSON Lines, one step per line, oldest first.
+    pub fn write_json_lines(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        for entry in &self.entries {
+            writeln!(writer, "{}", entry.to_json())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TraceEntry {
+    pub fn to_json(&self) -> String {
+        let step = &self.step;
+
+        let registers = step
+            .register_changes
+            .iter()
+            .map(|change| {
+                format!(
+                    r#"{{"register":"{:?}","old":{},"new":{}}}"#,
+                    change.register, change.old, change.new
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let memory = step
+            .memory_writes
+            .iter()
+            .map(|write| {
+                format!(
+                    r#"{{"address":{},"old":{},"new":{}}}"#,
+                    write.address, write.old, write.new
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let stack = step
+            .stack_writes
+            .iter()
+            .map(|write| {
+                format!(
+                    r#"{{"index":{},"old":{},"new":{}}}"#,
+                    write.index, write.old, write.new
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            r#"{{"tick":{},"main":{},"neighbor":{},"pc":{},"opcode":{},"instruction":"{:?}","registers":[{registers}],"memory":[{memory}],"stack":[{stack}],"turn_over":{}}}"#,
+            self.tick,
+            self.main_index,
+            self.neighbor_index,
+            step.pc,
+            step.opcode,
+            step.instruction,
+            step.is_turn_over,
+        )
+    }
+}
+
+#[test]
+fn test_tracer() {
+    let context = |main_index, neighbor_index, tick| PairContext {
+        main_index,
+        neighbor_index,
+        tick,
+        ..Default::default()
+    };
+    let step = |pc| StepRecord {
+        pc,
+        opcode: 0,
+        instruction: InstructionNop.into(),
+        register_changes: Vec::new(),
+        memory_writes: Vec::new(),
+        stack_writes: Vec::new(),
+        is_turn_over: false,
+    };
+
+    let mut tracer = Tracer::new(2).with_cells([3, 7]);
+    assert!(tracer.is_traced(&context(3, 4, 0)));
+    assert!(tracer.is_traced(&context(6, 7, 0)));
+    assert!(!tracer.is_traced(&context(4, 5, 0)));
+
+    for pc in 0..3 {
+        tracer.record(&context(3, 4, pc as u64), step(pc));
+    }
+    let pcs = tracer
+        .entries
+        .iter()
+        .map(|entry| (entry.tick, entry.step.pc))
+        .collect::<Vec<_>>();
+    assert_eq!(pcs, [(1, 1), (2, 2)]);
+
+    let mut world = World::with_seed(AreaSize::splat(4), VmConfig::default(), 0);
+    world.tracer = Some(Tracer::new(1000).with_cells([5]));
+    world.tick();
+    let tracer = world.tracer.as_ref().unwrap();
+    assert!(!tracer.entries.is_empty());
+    assert!(tracer
+        .entries
+        .iter()
+        .all(|entry| entry.main_index == 5 || entry.neighbor_index == 5));
+}
+
+#[test]
+fn test_trace_entry_to_json() {
+    let entry = TraceEntry {
+        tick: 12,
+        main_index: 3,
+        neighbor_index: 4,
+        step: StepRecord {
+            pc: 0x10,
+            opcode: 0xa0,
+            instruction: InstructionNop.into(),
+            register_changes: vec![RegisterChange {
+                register: Register::ProgramCounter,
+                old: 0x10,
+                new: 0x11,
+            }],
+            memory_writes: vec![MemoryWrite {
+                address: 0x90,
+                old: 1,
+                new: 2,
+            }],
+            stack_writes: vec![StackWrite {
+                index: 3,
+                old: 0,
+                new: 5,
+            }],
+            is_turn_over: true,
+        },
+    };
+
+    assert_eq!(
+        entry.to_json(),
+        r#"{"tick":12,"main":3,"neighbor":4,"pc":16,"opcode":160,"instruction":"Nop(InstructionNop)","registers":[{"register":"ProgramCounter","old":16,"new":17}],"memory":[{"address":144,"old":1,"new":2}],"stack":[{"index":3,"old":0,"new":5}],"turn_over":true}"#
+    );
+
+    let mut tracer = Tracer::new(4);
+    tracer.entries.push_back(entry.clone());
+    tracer.entries.push_back(entry);
+    let mut output = Vec::new();
+    tracer.write_json_lines(&mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+}
@@ -1,4 +1,5 @@
 use crate::*;
+use ::rand::{rngs::SmallRng, SeedableRng};
 use macroquad::prelude::*;
 use rayon::prelude::*;
 
@@ -7,18 +8,56 @@ pub struct World {
     pub cells: Vec<CellState>,
     pub update_stage: UpdateState,
     pub cell_cycles_per_tick: usize,
+    pub vm_config: VmConfig,
+    /// Seed of the initial cells and of the per-pair random generators.
+    pub seed: u64,
+    /// Number of ticks since the world was created.
+    pub tick_count: u64,
+    pub last_tick_stats: TickStats,
+    /// Opt-in recorder of executed steps of selected pairs.
+    pub tracer: Option<Tracer>,
+    /// Opt-in recorder of soup-wide metrics.
+    pub metrics: Option<MetricsRecorder>,
+    /// Opt-in phylogeny of genomes copied between cells.
+    pub lineage: Option<LineageTracker>,
+    /// Index of the cell each cell was paired with during the last tick it took part in.
+    pub last_partners: Vec<Option<u32>>,
 }
 
 impl World {
     pub fn new(size: AreaSize) -> Self {
-        assert!(size.width % 2 == 0, "World width must be even");
-        assert!(size.height % 2 == 0, "World height must be even");
+        Self::with_config(size, VmConfig::default())
+    }
+
+    pub fn with_config(size: AreaSize, vm_config: VmConfig) -> Self {
+        Self::with_seed(size, vm_config, ::rand::random())
+    }
+
+    /// Create world which evolves the same way for the same seed.
+    pub fn with_seed(size: AreaSize, vm_config: VmConfig, seed: u64) -> Self {
+        assert!(size.width.is_multiple_of(2), "World width must be even");
+        assert!(size.height.is_multiple_of(2), "World height must be even");
+        if let Err(err) = vm_config.validate() {
+            panic!("Invalid VM config: {err}");
+        }
+
+        let mut rng = SmallRng::seed_from_u64(seed);
 
         Self {
             size,
-            cells: (0..size.area()).map(|_| CellState::random()).collect(),
+            cells: (0..size.area())
+                .map(|_| CellState::random(&vm_config, &mut rng))
+                .collect(),
             update_stage: UpdateState::Vertical { reversed: false },
             cell_cycles_per_tick: 256,
+            vm_config,
+            seed,
+            tick_count: 0,
+            last_tick_stats: TickStats::default(),
+            tracer: None,
+            metrics: None,
+            lineage: None,
+            last_partners: vec![None; size.area()],
         }
     }
 
@@ -27,48 +66,121 @@ impl World {
         let update_stage = self.update_stage;
         self.update_stage = update_stage.next();
 
+        let tick = self.tick_count;
+        self.tick_count += 1;
+
         let mut pairs = Vec::<CellPair<'static>>::with_capacity(self.size.area() / 2);
 
         // update stage 0
         for i in update_stage.get_i_range(self.size) {
             for j in update_stage.get_j_range(self.size) {
                 let (main_index, neighbor_index) = update_stage.get_indices(self.size, i, j);
+                self.last_partners[main_index] = Some(neighbor_index as u32);
+                self.last_partners[neighbor_index] = Some(main_index as u32);
 
                 let (main_cell, neighbor_cell) =
                     get_pair_mut(&mut self.cells, main_index, neighbor_index);
 
-                let pair = CellPair::new(main_cell, neighbor_cell);
+                let mut pair = CellPair::new(main_cell, neighbor_cell, self.vm_config)
+                    .with_context(PairContext {
+                        main_index: main_index as u32,
+                        neighbor_index: neighbor_index as u32,
+                        main_position: self.size.index_to_coords(main_index),
+                        direction: update_stage.get_direction(),
+                        tick,
+                    })
+                    .with_seed(pair_seed(self.seed, tick, main_index));
+                if self.lineage.is_some() {
+                    pair = pair.with_neighbor_write_log();
+                }
 
                 // Safety: we will drop the references before this function returns
                 pairs.push(unsafe { std::mem::transmute::<CellPair<'_>, CellPair<'static>>(pair) });
             }
         }
 
-        pairs.par_iter_mut().for_each(CellPair::tick);
+        let tracer = self.tracer.as_ref();
+        let traces = pairs
+            .par_iter_mut()
+            .filter_map(|pair| {
+                if !tracer.is_some_and(|tracer| tracer.is_traced(&pair.context)) {
+                    pair.tick();
+                    return None;
+                }
+
+                let mut steps = Vec::new();
+                loop {
+                    let step = pair.step();
+                    let is_turn_over = step.is_turn_over;
+                    steps.push(step);
+
+                    if is_turn_over {
+                        break;
+                    }
+                }
+
+                Some((pair.context, steps))
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(tracer) = &mut self.tracer {
+            for (context, steps) in traces {
+                for step in steps {
+                    tracer.record(&context, step);
+                }
+            }
+        }
+
+        self.last_tick_stats = TickStats::default();
+        for mut pair in pairs {
+            if pair.stats.neighbor_writes > 0 {
+                pair.stats.first_neighbor_writer = Some(pair.context.main_index);
+
+                if let Some(lineage) = &mut self.lineage {
+                    let written = pair.take_neighbor_write_log();
+                    let neighbor_index = pair.context.neighbor_index;
+                    lineage.observe(pair.main, pair.neighbor, neighbor_index, &written, tick);
+                }
+            }
+
+            self.last_tick_stats += pair.stats;
+        }
+
+        self.record_metrics();
     }
 
     /// Returns the size of the render area.
     pub fn get_image_size(&self) -> AreaSize {
-        self.size * CellState::CANVAS_SIZE
+        self.size * self.vm_config.cell_canvas_size()
     }
 
     pub fn draw_to_image(&self, image: &mut Image) {
+        let cell_canvas_size = self.vm_config.cell_canvas_size();
+
         for (index, cell) in self.cells.iter().enumerate() {
             let pos = self.size.index_to_coords(index);
 
-            let cell_pos = pos * CellState::CANVAS_SIZE;
-            cell.draw_to_image(image, cell_pos);
+            let cell_pos = pos * cell_canvas_size;
+            cell.draw_to_image(image, cell_pos, &self.vm_config);
         }
 
         image.set_pixel(0, 0, RED);
         image.set_pixel(
-            (self.size.width * CellState::CANVAS_SIZE.width) as u32 - 1,
-            (self.size.height * CellState::CANVAS_SIZE.height) as u32 - 1,
+            (self.size.width * cell_canvas_size.width) as u32 - 1,
+            (self.size.height * cell_canvas_size.height) as u32 - 1,
             GREEN,
         );
     }
 }
 
+/// Mix world seed, tick and cell index into the seed of the pair's random generator.
+#[inline(always)]
+fn pair_seed(world_seed: u64, tick: u64, main_index: usize) -> u64 {
+    world_seed
+        ^ tick.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (main_index as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UpdateState {
     Vertical { reversed: bool },
@@ -89,6 +201,26 @@ impl UpdateState {
         }
     }
 
+    /// Direction from the main cell to the neighbor.
+    pub fn get_direction(self) -> Direction {
+        match self {
+            Self::Vertical { reversed } | Self::VerticalOffset { reversed } => {
+                if reversed {
+                    Direction::Down
+                } else {
+                    Direction::Up
+                }
+            }
+            Self::Horizontal { reversed } | Self::HorizontalOffset { reversed } => {
+                if reversed {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                }
+            }
+        }
+    }
+
     pub fn get_i_range(self, world_size: AreaSize) -> std::ops::Range<u32> {
         match self {
             Self::Vertical { .. } | Self::VerticalOffset { .. } => 0..world_size.width as u32,
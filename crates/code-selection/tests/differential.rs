@@ -0,0 +1,203 @@
+//! Differential fuzzing of [`CellPair::tick`] against the reference interpreter.
+//!
+//! Every run starts from random memory, registers, tags and stack, so the whole opcode map
+//! is covered. Runs are seeded and reproducible, set `DIFFERENTIAL_ITERATIONS` to fuzz longer.
+
+mod reference;
+
+use code_selection::*;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use reference::Machine;
+
+const DEFAULT_ITERATIONS: usize = 500;
+
+fn configs() -> Vec<VmConfig> {
+    let v3 = VmConfig {
+        opcode_revision: OpcodeRevision::V3,
+        ..Default::default()
+    };
+    let bits16 = VmConfig {
+        memory_size: 300,
+        address_mode: AddressMode::Bits16,
+        ..v3
+    };
+
+    vec![
+        VmConfig::default(),
+        v3,
+        VmConfig {
+            memory_size: 100,
+            ..v3
+        },
+        VmConfig {
+            write_protection: Some(ProtectionPenalty::DropWrite),
+            ..v3
+        },
+        VmConfig {
+            write_protection: Some(ProtectionPenalty::Cycles(5)),
+            ..v3
+        },
+        VmConfig {
+            write_protection: Some(ProtectionPenalty::EndTurn),
+            ..v3
+        },
+        VmConfig {
+            stack_mode: StackMode::Separate { size: 16 },
+            ..v3
+        },
+        VmConfig {
+            stack_mode: StackMode::Trap { size: 16 },
+            ..v3
+        },
+        bits16,
+        VmConfig {
+            write_protection: Some(ProtectionPenalty::Cycles(3)),
+            stack_mode: StackMode::Trap { size: 300 },
+            ..bits16
+        },
+    ]
+}
+
+/// Random cell with random protection tags and stack contents allowed by the config.
+fn random_cell(config: &VmConfig, context: &PairContext, rng: &mut impl Rng) -> CellState {
+    let mut cell = CellState::random(config, rng);
+
+    if config.write_protection.is_some() && rng.gen_bool(0.8) {
+        let owners = [context.main_index, context.neighbor_index, 999];
+        for index in 0..config.memory_size {
+            if rng.gen_bool(0.2) {
+                *cell.get_tag_mut(index) = MemoryTag {
+                    owner: owners[rng.gen_range(0..owners.len())],
+                    is_protected: rng.gen(),
+                };
+            }
+        }
+    }
+
+    if let StackMode::Separate { size } | StackMode::Trap { size } = config.stack_mode {
+        if rng.gen() {
+            cell.stack = (0..size)
+                .map(|_| rng.gen::<Word>() & config.address_mode.word_mask())
+                .collect();
+        }
+    }
+
+    cell
+}
+
+/// Tags of the cell with lazily allocated tags filled in.
+fn tags(cell: &CellState) -> Vec<MemoryTag> {
+    if cell.tags.is_empty() {
+        vec![MemoryTag::default(); cell.memory.len()]
+    } else {
+        cell.tags.clone()
+    }
+}
+
+fn run_case(config: VmConfig, seed: u64) -> Result<(), String> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+
+    let context = PairContext {
+        main_index: rng.gen_range(0..4),
+        neighbor_index: rng.gen_range(0..4),
+        main_position: RelativePosition::new(rng.gen_range(0..300), rng.gen_range(0..300)),
+        direction: [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ][rng.gen_range(0..4)],
+        tick: rng.gen(),
+    };
+    let cycles = rng.gen_range(1..64);
+    let pair_seed = rng.gen();
+
+    let mut main = random_cell(&config, &context, &mut rng);
+    let mut neighbor = random_cell(&config, &context, &mut rng);
+
+    let mut machine = Machine::new(config, context, pair_seed, cycles - 1);
+    machine.memory = [main.memory.as_slice(), &neighbor.memory].concat();
+    machine.registers = main.registers.map(u32::from);
+    machine.tags = [tags(&main), tags(&neighbor)].concat();
+    if !main.stack.is_empty() {
+        machine.stack = main.stack.iter().copied().map(u32::from).collect();
+    }
+
+    machine.run();
+
+    let mut pair = CellPair::new(&mut main, &mut neighbor, config)
+        .with_context(context)
+        .with_seed(pair_seed);
+    pair.cycles_to_run = cycles - 1;
+    pair.tick();
+    let stats = pair.stats;
+
+    let mut mismatches = Vec::new();
+    let mut check = |name: &str, actual: String, expected: String| {
+        if actual != expected {
+            mismatches.push(format!(
+                "{name}:\n  tick:      {actual}\n  reference: {expected}"
+            ));
+        }
+    };
+
+    check(
+        "registers",
+        format!("{:02x?}", main.registers),
+        format!("{:02x?}", machine.registers),
+    );
+    check(
+        "memory",
+        format!(
+            "{:02x?}",
+            [main.memory.as_slice(), &neighbor.memory].concat()
+        ),
+        format!("{:02x?}", machine.memory),
+    );
+    check(
+        "tags",
+        format!("{:?}", [tags(&main), tags(&neighbor)].concat()),
+        format!("{:?}", machine.tags),
+    );
+    if !main.stack.is_empty() || machine.stack.iter().any(|&value| value != 0) {
+        check(
+            "stack",
+            format!("{:02x?}", main.stack),
+            format!("{:02x?}", machine.stack),
+        );
+    }
+    check(
+        "stats",
+        format!(
+            "{} traps, {} neighbor writes",
+            stats.stack_traps, stats.neighbor_writes
+        ),
+        format!(
+            "{} traps, {} neighbor writes",
+            machine.stack_traps, machine.neighbor_writes
+        ),
+    );
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches.join("\n"))
+    }
+}
+
+#[test]
+fn test_tick_matches_reference() {
+    let iterations = std::env::var("DIFFERENTIAL_ITERATIONS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_ITERATIONS);
+
+    for config in configs() {
+        for iteration in 0..iterations {
+            let seed = iteration as u64;
+            if let Err(err) = run_case(config, seed) {
+                panic!("{config:?}, seed {seed}:\n{err}");
+            }
+        }
+    }
+}
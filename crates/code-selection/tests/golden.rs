@@ -0,0 +1,237 @@
+//! Golden tests of instruction semantics.
+//!
+//! Every `tests/golden/*.case` file is a header of `key: value` lines followed by the assembly
+//! of the main cell and optionally of the neighbor:
+//!
+//! ```text
+//! ; comment
+//! cycles: 2
+//! main: a=0xff b=0x01
+//! expect main: a=0x00 f=0x05
+//! expect memory 0x80: 2a 00
+//! --- main
+//! add a, b
+//! --- neighbor
+//! db 0x2a
+//! ```
+//!
+//! Supported keys:
+//! - `cycles` number of instructions to run, the run stops earlier if the turn ends
+//! - `memory-size`, `address-mode` (`8` or `16`), `revision`, `stack`, `protection`, `seed`
+//!   configure the pair, see [`VmConfig`]
+//! - `main` and `neighbor` initial registers of the cells, zero by default
+//! - `expect main` and `expect neighbor` final values of the listed registers
+//! - `expect memory <address>` final bytes of the pair memory starting at the address
+
+use code_selection::*;
+use std::path::Path;
+
+struct Case {
+    cycles: usize,
+    seed: u64,
+    config: VmConfig,
+    registers: [Vec<(Register, Word)>; 2],
+    expected_registers: [Vec<(Register, Word)>; 2],
+    expected_memory: Vec<(usize, Vec<u8>)>,
+    programs: [String; 2],
+}
+
+impl Case {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut case = Case {
+            cycles: 0,
+            seed: 0,
+            config: VmConfig::default(),
+            registers: Default::default(),
+            expected_registers: Default::default(),
+            expected_memory: Vec::new(),
+            programs: Default::default(),
+        };
+
+        let mut program = None;
+        for line in source.lines() {
+            if let Some(cell) = line.strip_prefix("---") {
+                program = Some(match cell.trim() {
+                    "main" => 0,
+                    "neighbor" => 1,
+                    cell => return Err(format!("unknown program section `{cell}`")),
+                });
+                continue;
+            }
+
+            if let Some(index) = program {
+                case.programs[index] += line;
+                case.programs[index] += "\n";
+                continue;
+            }
+
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("expected `key: value`, got `{line}`"))?;
+            let value = value.trim();
+
+            match key.trim() {
+                "cycles" => case.cycles = parse_number(value)? as usize,
+                "seed" => case.seed = parse_number(value)?,
+                "memory-size" => case.config.memory_size = parse_number(value)? as usize,
+                "address-mode" => case.config.address_mode = value.parse()?,
+                "revision" => case.config.opcode_revision = value.parse()?,
+                "stack" => case.config.stack_mode = value.parse()?,
+                "protection" => case.config.write_protection = Some(value.parse()?),
+                "main" => case.registers[0] = parse_registers(value)?,
+                "neighbor" => case.registers[1] = parse_registers(value)?,
+                "expect main" => case.expected_registers[0] = parse_registers(value)?,
+                "expect neighbor" => case.expected_registers[1] = parse_registers(value)?,
+                key => {
+                    let address = key
+                        .strip_prefix("expect memory")
+                        .ok_or_else(|| format!("unknown key `{key}`"))?;
+                    let bytes = value
+                        .split_whitespace()
+                        .map(|byte| {
+                            u8::from_str_radix(byte, 16)
+                                .map_err(|err| format!("invalid byte `{byte}`: {err}"))
+                        })
+                        .collect::<Result<_, _>>()?;
+
+                    case.expected_memory
+                        .push((parse_number(address.trim())? as usize, bytes));
+                }
+            }
+        }
+
+        if case.cycles == 0 {
+            return Err("`cycles` must be positive".to_owned());
+        }
+
+        Ok(case)
+    }
+
+    /// Assemble the program and set initial registers of the cell, 0 is main, 1 is neighbor.
+    fn load_cell(&self, index: usize) -> Result<CellState, String> {
+        let config = &self.config;
+
+        let mut memory = assemble(&self.programs[index], config).map_err(|err| err.to_string())?;
+        if memory.len() > config.memory_size {
+            return Err(format!(
+                "program is longer than {} bytes",
+                config.memory_size
+            ));
+        }
+        memory.resize(config.memory_size, 0);
+
+        let mut registers = [0; CellState::REGISTERS_COUNT];
+        for &(register, value) in &self.registers[index] {
+            registers[register as usize] = value;
+        }
+
+        Ok(CellState {
+            memory,
+            registers,
+            tags: Vec::new(),
+            stack: Vec::new(),
+            lineage: None,
+            provenance: None,
+        })
+    }
+
+    /// Run the case and return the list of mismatches.
+    fn run(&self) -> Result<Vec<String>, String> {
+        let mut main = self.load_cell(0)?;
+        let mut neighbor = self.load_cell(1)?;
+
+        let mut pair = CellPair::new(&mut main, &mut neighbor, self.config).with_seed(self.seed);
+        pair.cycles_to_run = self.cycles - 1;
+        pair.tick();
+
+        let mut mismatches = Vec::new();
+        let cells = [("main", &main), ("neighbor", &neighbor)];
+        for ((name, cell), expected) in cells.iter().zip(&self.expected_registers) {
+            for &(register, value) in expected {
+                let actual = cell.registers[register as usize];
+                if actual != value {
+                    mismatches.push(format!(
+                        "{name} {register}: expected {value:#04x}, got {actual:#04x}"
+                    ));
+                }
+            }
+        }
+
+        let memory = [main.memory, neighbor.memory].concat();
+        for (address, expected) in &self.expected_memory {
+            let actual = memory
+                .get(*address..*address + expected.len())
+                .ok_or_else(|| format!("memory range at {address:#04x} is out of bounds"))?;
+
+            if actual != expected.as_slice() {
+                mismatches.push(format!(
+                    "memory at {address:#04x}: expected {expected:02x?}, got {actual:02x?}"
+                ));
+            }
+        }
+
+        Ok(mismatches)
+    }
+}
+
+fn parse_number(value: &str) -> Result<u64, String> {
+    let result = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    result.map_err(|err| format!("invalid number `{value}`: {err}"))
+}
+
+fn parse_registers(value: &str) -> Result<Vec<(Register, Word)>, String> {
+    value
+        .split_whitespace()
+        .map(|register| {
+            let (name, value) = register
+                .split_once('=')
+                .ok_or_else(|| format!("expected `<name>=<value>`, got `{register}`"))?;
+
+            Ok((name.parse()?, parse_number(value)? as Word))
+        })
+        .collect()
+}
+
+#[test]
+fn test_golden_cases() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut paths = std::fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "case"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "no cases in {}", directory.display());
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let name = path.file_stem().unwrap().to_string_lossy();
+        let source = std::fs::read_to_string(path).unwrap();
+
+        match Case::parse(&source).and_then(|case| case.run()) {
+            Ok(mismatches) => {
+                for mismatch in mismatches {
+                    failures.push(format!("{name}: {mismatch}"));
+                }
+            }
+            Err(err) => failures.push(format!("{name}: {err}")),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden cases failed:\n{}",
+        failures.len(),
+        paths.len(),
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,538 @@
+//! Deliberately simple reference interpreter of the instruction set.
+//!
+//! Opcodes are decoded by [`SPEC`], a table of `(mask, pattern, revision, operation)` rows
+//! where the first row with `opcode & mask == pattern` available in the revision wins.
+//! The low 3 bits of the opcode select the register of the operation. Nothing here is
+//! optimized, every rule is spelled out once so it can be checked against `CellPair`.
+
+use code_selection::{
+    AddressMode, MemoryTag, OpcodeRevision, PairContext, ProtectionPenalty, StackMode, VmConfig,
+};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const A: usize = 0;
+const F: usize = 1;
+const PC: usize = 2;
+const SP: usize = 3;
+
+const FLAG_Z: u32 = 0b001;
+const FLAG_N: u32 = 0b010;
+const FLAG_C: u32 = 0b100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Nop,
+    /// a = reg
+    LoadAReg,
+    /// [a] = reg
+    LoadAtAReg,
+    /// reg = [a]
+    LoadRegAtA,
+    /// a = imm
+    LoadAImm,
+    /// reg = a
+    LoadRegA,
+    /// a = a + reg
+    AddReg,
+    /// a = a + [reg]
+    AddAtReg,
+    SubReg,
+    SubAtReg,
+    AndReg,
+    AndAtReg,
+    OrReg,
+    OrAtReg,
+    XorReg,
+    XorAtReg,
+    NotReg,
+    NotAtReg,
+    /// push pc, pc = reg
+    JumpReg,
+    /// push pc, pc = word at [reg]
+    JumpAtReg,
+    JumpZReg,
+    JumpZAtReg,
+    /// push reg
+    PushReg,
+    /// push [reg]
+    PushAtReg,
+    /// reg = pop
+    PopReg,
+    /// [reg] = pop
+    PopAtReg,
+    Protect,
+    Unprotect,
+    SenseDirection,
+    SenseX,
+    SenseY,
+    SenseTick,
+    SenseRole,
+    /// reg = random byte
+    Random,
+    /// push pc, pc = reg
+    CallReg,
+    CallZReg,
+    ShiftLeftReg,
+    ShiftLeftAtReg,
+    ShiftRightReg,
+    ShiftRightAtReg,
+    /// flags of a - imm
+    CompareAImm,
+    /// flags of a - reg
+    CompareAReg,
+    /// flags of [a] - imm
+    CompareAtAImm,
+    Replicate,
+    /// push pc, pc = imm
+    JumpImm,
+    /// if z { push pc, pc = imm }, the immediate is skipped only when taken
+    JumpZImm,
+    CallZImm,
+    CallImm,
+    Return,
+    ReturnZ,
+}
+
+use OpcodeRevision::*;
+
+#[rustfmt::skip]
+#[allow(clippy::unusual_byte_groupings)]
+const SPEC: &[(u8, u8, OpcodeRevision, Op)] = &[
+    (0b11111_111, 0b00000_000, V0, Op::Nop),
+    (0b11111_000, 0b00000_000, V0, Op::LoadAReg),
+    (0b11111_000, 0b00001_000, V0, Op::LoadAtAReg),
+    (0b11111_000, 0b00010_000, V0, Op::LoadRegAtA),
+    (0b11111_111, 0b00011_000, V0, Op::LoadAImm),
+    (0b11111_000, 0b00011_000, V0, Op::LoadRegA),
+    (0b11111_000, 0b00100_000, V0, Op::AddReg),
+    (0b11111_000, 0b00101_000, V0, Op::AddAtReg),
+    (0b11111_000, 0b00110_000, V0, Op::SubReg),
+    (0b11111_000, 0b00111_000, V0, Op::SubAtReg),
+    (0b11111_000, 0b01000_000, V0, Op::AndReg),
+    (0b11111_000, 0b01001_000, V0, Op::AndAtReg),
+    (0b11111_000, 0b01010_000, V0, Op::OrReg),
+    (0b11111_000, 0b01011_000, V0, Op::OrAtReg),
+    (0b11111_000, 0b01100_000, V0, Op::XorReg),
+    (0b11111_000, 0b01101_000, V0, Op::XorAtReg),
+    (0b11111_000, 0b01110_000, V0, Op::NotReg),
+    (0b11111_000, 0b01111_000, V0, Op::NotAtReg),
+    (0b11111_000, 0b10000_000, V0, Op::JumpReg),
+    (0b11111_000, 0b10001_000, V0, Op::JumpAtReg),
+    (0b11111_000, 0b10010_000, V0, Op::JumpZReg),
+    (0b11111_000, 0b10011_000, V0, Op::JumpZAtReg),
+    (0b11111_000, 0b10100_000, V0, Op::PushReg),
+    (0b11111_000, 0b10101_000, V0, Op::PushAtReg),
+    (0b11111_000, 0b10110_000, V0, Op::PopReg),
+    (0b11111_000, 0b10111_000, V0, Op::PopAtReg),
+    (0b11111_111, 0b11000_000, V1, Op::Protect),
+    (0b11111_111, 0b11000_001, V1, Op::Unprotect),
+    (0b11111_111, 0b11000_010, V3, Op::SenseDirection),
+    (0b11111_111, 0b11000_011, V3, Op::SenseX),
+    (0b11111_111, 0b11000_100, V3, Op::SenseY),
+    (0b11111_111, 0b11000_101, V3, Op::SenseTick),
+    (0b11111_111, 0b11000_110, V3, Op::SenseRole),
+    (0b11111_000, 0b11001_000, V2, Op::Random),
+    (0b11111_000, 0b11000_000, V0, Op::CallReg),
+    (0b11111_000, 0b11001_000, V0, Op::CallZReg),
+    (0b11111_000, 0b11010_000, V0, Op::ShiftLeftReg),
+    (0b11111_000, 0b11011_000, V0, Op::ShiftLeftAtReg),
+    (0b11111_000, 0b11100_000, V0, Op::ShiftRightReg),
+    (0b11111_000, 0b11101_000, V0, Op::ShiftRightAtReg),
+    (0b11111_111, 0b11110_000, V0, Op::CompareAImm),
+    (0b11111_000, 0b11110_000, V0, Op::CompareAReg),
+    (0b11111_111, 0b11111_000, V0, Op::CompareAtAImm),
+    (0b11111_111, 0b11111_001, V0, Op::Replicate),
+    (0b11111_111, 0b11111_010, V0, Op::JumpImm),
+    (0b11111_111, 0b11111_011, V0, Op::JumpZImm),
+    (0b11111_111, 0b11111_100, V0, Op::CallZImm),
+    (0b11111_111, 0b11111_101, V0, Op::CallImm),
+    (0b11111_111, 0b11111_110, V0, Op::Return),
+    (0b11111_111, 0b11111_111, V0, Op::ReturnZ),
+];
+
+fn decode(opcode: u8, revision: OpcodeRevision) -> Op {
+    SPEC.iter()
+        .find(|(mask, pattern, since, _)| opcode & mask == *pattern && revision >= *since)
+        .map(|(_, _, _, op)| *op)
+        .expect("every opcode is covered by the spec")
+}
+
+/// Both cells of the pair flattened into a single address space.
+pub struct Machine {
+    pub config: VmConfig,
+    pub context: PairContext,
+    /// Main cell memory followed by the neighbor memory.
+    pub memory: Vec<u8>,
+    /// Registers of the main cell.
+    pub registers: [u32; 8],
+    /// Tag of every byte of [`Machine::memory`].
+    pub tags: Vec<MemoryTag>,
+    /// Stack of the main cell in separate and trap modes.
+    pub stack: Vec<u32>,
+    /// Number of instructions left after the current one.
+    pub cycles: usize,
+    pub rng: SmallRng,
+    pub stack_traps: usize,
+    pub neighbor_writes: usize,
+}
+
+impl Machine {
+    pub fn new(config: VmConfig, context: PairContext, seed: u64, cycles: usize) -> Self {
+        let stack_size = match config.stack_mode {
+            StackMode::Wrap => 0,
+            StackMode::Separate { size } | StackMode::Trap { size } => size,
+        };
+
+        Self {
+            config,
+            context,
+            memory: vec![0; config.address_space()],
+            registers: [0; 8],
+            tags: vec![MemoryTag::default(); config.address_space()],
+            stack: vec![0; stack_size],
+            cycles,
+            rng: SmallRng::seed_from_u64(seed),
+            stack_traps: 0,
+            neighbor_writes: 0,
+        }
+    }
+
+    fn mask(&self) -> u32 {
+        match self.config.address_mode {
+            AddressMode::Bits8 => 0xFF,
+            AddressMode::Bits16 => 0xFFFF,
+        }
+    }
+
+    fn word_bytes(&self) -> u32 {
+        match self.config.address_mode {
+            AddressMode::Bits8 => 1,
+            AddressMode::Bits16 => 2,
+        }
+    }
+
+    fn wrap(&self, address: u32) -> usize {
+        address as usize % self.memory.len()
+    }
+
+    fn set(&mut self, register: usize, value: u32) {
+        self.registers[register] = value & self.mask();
+    }
+
+    fn flag(&self, flag: u32) -> bool {
+        self.registers[F] & flag != 0
+    }
+
+    fn set_flag(&mut self, flag: u32, value: bool) {
+        if value {
+            self.set(F, self.registers[F] | flag);
+        } else {
+            self.set(F, self.registers[F] & !flag);
+        }
+    }
+
+    fn set_flags(&mut self, z: bool, n: bool, c: bool) {
+        self.set_flag(FLAG_Z, z);
+        self.set_flag(FLAG_N, n);
+        self.set_flag(FLAG_C, c);
+    }
+
+    fn read(&self, address: u32) -> u32 {
+        self.memory[self.wrap(address)] as u32
+    }
+
+    /// Little endian register sized value.
+    fn read_word(&self, address: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..self.word_bytes() {
+            value |= self.read(address.wrapping_add(i) & 0xFFFF) << (8 * i);
+        }
+        value
+    }
+
+    fn write(&mut self, address: u32, value: u32) {
+        let address = self.wrap(address);
+
+        if let Some(penalty) = self.config.write_protection {
+            if !self.tags[address].is_writable_by(self.context.main_index) {
+                match penalty {
+                    ProtectionPenalty::DropWrite => {}
+                    ProtectionPenalty::Cycles(cycles) => {
+                        self.cycles = self.cycles.saturating_sub(cycles)
+                    }
+                    ProtectionPenalty::EndTurn => self.cycles = 0,
+                }
+                return;
+            }
+        }
+
+        if address >= self.config.memory_size {
+            self.neighbor_writes += 1;
+        }
+        self.memory[address] = value as u8;
+    }
+
+    fn write_word(&mut self, address: u32, value: u32) {
+        for i in 0..self.word_bytes() {
+            self.write(address.wrapping_add(i) & 0xFFFF, value >> (8 * i));
+        }
+    }
+
+    fn fetch(&mut self) -> u32 {
+        let value = self.read(self.registers[PC]);
+        self.set(PC, self.registers[PC] + 1);
+        value
+    }
+
+    fn fetch_word(&mut self) -> u32 {
+        let value = self.read_word(self.registers[PC]);
+        self.set(PC, self.registers[PC] + self.word_bytes());
+        value
+    }
+
+    fn trap(&mut self) {
+        self.cycles = 0;
+        self.stack_traps += 1;
+    }
+
+    /// Returns false if the push trapped.
+    fn push(&mut self, value: u32) -> bool {
+        let sp = self.registers[SP] as usize;
+
+        match self.config.stack_mode {
+            StackMode::Wrap => {
+                self.set(SP, (sp as u32).wrapping_sub(self.word_bytes()));
+                self.write_word(self.registers[SP], value);
+            }
+            StackMode::Separate { size } => {
+                let sp = (sp + size - 1) % size;
+                self.set(SP, sp as u32);
+                self.stack[sp] = value;
+            }
+            StackMode::Trap { size } => {
+                if sp == 0 || sp > size {
+                    self.trap();
+                    return false;
+                }
+                self.set(SP, sp as u32 - 1);
+                self.stack[sp - 1] = value;
+            }
+        }
+
+        true
+    }
+
+    /// Returns `None` if the pop trapped.
+    fn pop(&mut self) -> Option<u32> {
+        let sp = self.registers[SP] as usize;
+
+        match self.config.stack_mode {
+            StackMode::Wrap => {
+                let value = self.read_word(sp as u32);
+                self.set(SP, sp as u32 + self.word_bytes());
+                Some(value)
+            }
+            StackMode::Separate { size } => {
+                let sp = sp % size;
+                self.set(SP, ((sp + 1) % size) as u32);
+                Some(self.stack[sp])
+            }
+            StackMode::Trap { size } => {
+                if sp >= size {
+                    self.trap();
+                    return None;
+                }
+                self.set(SP, sp as u32 + 1);
+                Some(self.stack[sp])
+            }
+        }
+    }
+
+    /// Push the return address and jump, unless the push trapped.
+    fn jump(&mut self, target: u32) {
+        if self.push(self.registers[PC]) {
+            self.set(PC, target);
+        }
+    }
+
+    /// Set flags of the arithmetic result and store it in the accumulator.
+    fn store_result(&mut self, result: u32, n: bool, c: bool) {
+        let result = result & self.mask();
+        self.set_flags(result == 0, n, c);
+        self.set(A, result);
+    }
+
+    /// Execute instructions until there are no cycles left.
+    pub fn run(&mut self) {
+        loop {
+            self.execute();
+
+            if self.cycles == 0 {
+                break;
+            }
+            self.cycles -= 1;
+        }
+    }
+
+    fn execute(&mut self) {
+        let opcode = self.fetch() as u8;
+        let reg = (opcode & 0b111) as usize;
+        let mask = self.mask();
+        let a = self.registers[A];
+        let r = self.registers[reg];
+        let z = self.flag(FLAG_Z);
+
+        match decode(opcode, self.config.opcode_revision) {
+            Op::Nop | Op::Replicate => {}
+
+            Op::LoadAReg => self.set(A, r),
+            Op::LoadAtAReg => self.write(a, r),
+            Op::LoadRegAtA => self.set(reg, self.read(a)),
+            Op::LoadAImm => {
+                let value = self.fetch_word();
+                self.set(A, value);
+            }
+            Op::LoadRegA => self.set(reg, a),
+
+            Op::AddReg => self.store_result(a + r, false, a + r > mask),
+            Op::AddAtReg => {
+                let value = self.read(r);
+                self.store_result(a + value, false, a + value > mask);
+            }
+            Op::SubReg => self.store_result(a.wrapping_sub(r), true, r > a),
+            Op::SubAtReg => {
+                let value = self.read(r);
+                self.store_result(a.wrapping_sub(value), true, value > a);
+            }
+            Op::AndReg => self.store_result(a & r, false, false),
+            Op::AndAtReg => self.store_result(a & self.read(r), false, false),
+            Op::OrReg => self.store_result(a | r, false, false),
+            Op::OrAtReg => self.store_result(a | self.read(r), false, false),
+            Op::XorReg => self.store_result(a ^ r, false, false),
+            Op::XorAtReg => self.store_result(a ^ self.read(r), false, false),
+
+            Op::NotReg => {
+                let result = !r & mask;
+                self.set_flags(result == 0, false, false);
+                self.set(reg, result);
+            }
+            // unary operations on [reg] set flags before the write, so the result of
+            // `not [f]`, `shl [f]` and `shr [f]` lands at the address of the new flags
+            Op::NotAtReg => {
+                let result = !self.read(r) & 0xFF;
+                self.set_flags(result == 0, false, false);
+                self.write(self.registers[reg], result);
+            }
+            Op::ShiftLeftReg => {
+                let result = (r << 1) & mask;
+                self.set_flags(result == 0, false, r & (mask ^ (mask >> 1)) != 0);
+                self.set(reg, result);
+            }
+            Op::ShiftLeftAtReg => {
+                let value = self.read(r);
+                let result = (value << 1) & 0xFF;
+                self.set_flags(result == 0, false, value & 0x80 != 0);
+                self.write(self.registers[reg], result);
+            }
+            Op::ShiftRightReg => {
+                self.set_flags(r >> 1 == 0, false, r & 1 != 0);
+                self.set(reg, r >> 1);
+            }
+            Op::ShiftRightAtReg => {
+                let value = self.read(r);
+                self.set_flags(value >> 1 == 0, false, value & 1 != 0);
+                self.write(self.registers[reg], value >> 1);
+            }
+
+            Op::CompareAImm => {
+                let value = self.fetch_word();
+                self.set_flags(a == value, true, a < value);
+            }
+            Op::CompareAReg => self.set_flags(a == r, true, a < r),
+            Op::CompareAtAImm => {
+                let value = self.fetch_word();
+                let byte = self.read(a);
+                self.set_flags(byte == value, true, byte < value);
+            }
+
+            Op::JumpReg | Op::CallReg => self.jump(r),
+            Op::JumpZReg | Op::CallZReg if z => self.jump(r),
+            Op::JumpAtReg => self.jump(self.read_word(r)),
+            Op::JumpZAtReg if z => self.jump(self.read_word(r)),
+            Op::JumpImm | Op::CallImm => {
+                let target = self.fetch_word();
+                self.jump(target);
+            }
+            Op::JumpZImm | Op::CallZImm if z => {
+                let target = self.fetch_word();
+                self.jump(target);
+            }
+            Op::JumpZReg | Op::CallZReg | Op::JumpZAtReg | Op::JumpZImm | Op::CallZImm => {}
+
+            Op::Return => {
+                if let Some(target) = self.pop() {
+                    self.set(PC, target);
+                }
+            }
+            Op::ReturnZ if z => {
+                if let Some(target) = self.pop() {
+                    self.set(PC, target);
+                }
+            }
+            Op::ReturnZ => {}
+
+            Op::PushReg => {
+                self.push(r);
+            }
+            Op::PushAtReg => {
+                self.push(self.read(r));
+            }
+            Op::PopReg => {
+                if let Some(value) = self.pop() {
+                    self.set(reg, value);
+                }
+            }
+            Op::PopAtReg => {
+                if let Some(value) = self.pop() {
+                    self.write(self.registers[reg], value);
+                }
+            }
+
+            Op::Protect | Op::Unprotect if self.config.write_protection.is_none() => {}
+            Op::Protect => {
+                let owner = self.context.main_index;
+                let address = self.wrap(a);
+                let is_owned = self.tags[address].is_writable_by(owner);
+                if is_owned {
+                    self.tags[address] = MemoryTag {
+                        owner,
+                        is_protected: true,
+                    };
+                }
+                self.set_flag(FLAG_Z, is_owned);
+            }
+            Op::Unprotect => {
+                let address = self.wrap(a);
+                let is_owned = self.tags[address].is_writable_by(self.context.main_index);
+                if is_owned {
+                    self.tags[address].is_protected = false;
+                }
+                self.set_flag(FLAG_Z, is_owned);
+            }
+
+            Op::SenseDirection => self.set(A, self.context.direction as u32),
+            Op::SenseX => self.set(A, self.context.main_position.x & 0xFF),
+            Op::SenseY => self.set(A, self.context.main_position.y & 0xFF),
+            Op::SenseTick => self.set(A, (self.context.tick & 0xFF) as u32),
+            Op::SenseRole => {
+                let address = self.wrap(self.registers[PC].wrapping_sub(1) & self.mask());
+                self.set(A, (address < self.config.memory_size) as u32);
+            }
+
+            Op::Random => {
+                let value = self.rng.gen::<u8>();
+                self.set(reg, value as u32);
+            }
+        }
+    }
+}